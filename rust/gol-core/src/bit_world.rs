@@ -0,0 +1,311 @@
+use crate::random::Random;
+use crate::rule::Rule;
+use crate::topology::Topology;
+use crate::universe::Universe;
+use crate::workers;
+use crate::world::{Settings, World, WorldError};
+
+/// A finite world that packs each row of cells into `u64` words and
+/// plays generations with bitwise adders instead of per cell lookups.
+///
+/// It covers the same `(0, 0)` to `(width, height)` locations as
+/// [`World`] and renders identically to it.
+#[derive(Clone)]
+pub struct BitWorld {
+  width: i64,
+  height: i64,
+  tick: u64,
+  rule: Rule,
+  topology: Topology,
+  threads: usize,
+
+  words_per_row: usize,
+  cells: Vec<u64>,
+}
+
+impl BitWorld {
+  /// Creates a Conway world and fills it with a random soup of cells.
+  pub fn new(width: i64, height: i64) -> Result<BitWorld, WorldError> {
+    BitWorld::with_settings(width, height, Settings::default())
+  }
+
+  /// Creates a world with the given settings and fills it with a random
+  /// soup of cells, the same soup [`World`] gets from those settings.
+  /// Negative sizes, and sizes with more cells than fit in memory, are
+  /// rejected.
+  pub fn with_settings(
+    width: i64,
+    height: i64,
+    settings: Settings,
+  ) -> Result<BitWorld, WorldError> {
+    let seed = settings.soup_seed();
+    let density = settings.density;
+    let mut world = BitWorld::empty(width, height, settings)?;
+    world.populate_cells(seed, density);
+    Ok(world)
+  }
+
+  fn empty(width: i64, height: i64, settings: Settings) -> Result<BitWorld, WorldError> {
+    // A byte a location is more than the bits rows are packed into
+    let (columns, rows) = Settings::check_size(width, height, 1)?;
+    let words_per_row = columns.div_ceil(64);
+    Ok(BitWorld {
+      width,
+      height,
+      tick: 0,
+      rule: settings.rule,
+      topology: settings.topology,
+      threads: settings.threads.max(1),
+      words_per_row,
+      cells: vec![0; words_per_row * rows],
+    })
+  }
+
+  fn populate_cells(&mut self, seed: u64, density: f64) {
+    let mut random = Random::new(seed);
+
+    for y in 0..=self.height {
+      for x in 0..=self.width {
+        let alive = random.next_alive(density);
+        self.set_cell(x, y, alive);
+      }
+    }
+  }
+
+  /// Returns the birth and survival rule of the world.
+  pub fn rule(&self) -> Rule {
+    self.rule
+  }
+
+  /// Returns how the edges of the world are joined together.
+  pub fn topology(&self) -> Topology {
+    self.topology
+  }
+
+  /// Returns how many worker threads play each generation.
+  pub fn threads(&self) -> usize {
+    self.threads
+  }
+
+  /// Sets how many worker threads play each generation, `1` plays on
+  /// the calling thread. The outcome is the same for any number.
+  pub fn set_threads(&mut self, threads: usize) {
+    self.threads = threads.max(1);
+  }
+
+  fn row(&self, y: i64) -> &[u64] {
+    let start = y as usize * self.words_per_row;
+    &self.cells[start..start + self.words_per_row]
+  }
+
+  fn set_cell(&mut self, x: i64, y: i64, alive: bool) {
+    let index = y as usize * self.words_per_row + x as usize / 64;
+    let bit = 1 << (x % 64);
+    if alive {
+      self.cells[index] |= bit;
+    } else {
+      self.cells[index] &= !bit;
+    }
+  }
+
+  // Looks up a location that may be past an edge, as the neighbours of
+  // edge cells are found in World
+  fn wrapped_alive_at(&self, x: i64, y: i64) -> bool {
+    match self.topology.wrap(x, y, self.width + 1, self.height + 1) {
+      Some((x, y)) => self.alive_at(x, y),
+      None => false,
+    }
+  }
+
+  // Returns the row `y` cells see as their own (`y` may be one past an
+  // edge), indexed by their column
+  fn neighbour_row(&self, y: i64) -> Vec<u64> {
+    if y >= 0 && y <= self.height {
+      return self.row(y).to_vec();
+    }
+
+    let mut row = vec![0; self.words_per_row];
+    for x in 0..=self.width {
+      if self.wrapped_alive_at(x, y) {
+        row[x as usize / 64] |= 1 << (x % 64);
+      }
+    }
+    row
+  }
+
+  // Shifts a row so every column holds its west neighbour, pulling the
+  // cell past the left edge in through the topology
+  fn west_of(&self, row: &[u64], y: i64) -> Vec<u64> {
+    let mut west: Vec<u64> = (0..row.len())
+      .map(|i| row[i] << 1 | if i > 0 { row[i - 1] >> 63 } else { 0 })
+      .collect();
+    west[0] |= self.wrapped_alive_at(-1, y) as u64;
+    west
+  }
+
+  // Shifts a row so every column holds its east neighbour, pulling the
+  // cell past the right edge in through the topology
+  fn east_of(&self, row: &[u64], y: i64) -> Vec<u64> {
+    let mut east: Vec<u64> = (0..row.len())
+      .map(|i| row[i] >> 1 | row.get(i + 1).map_or(0, |next| next << 63))
+      .collect();
+    let x = self.width;
+    east[x as usize / 64] &= !(1 << (x % 64));
+    east[x as usize / 64] |= (self.wrapped_alive_at(x + 1, y) as u64) << (x % 64);
+    east
+  }
+
+  fn next_row(&self, y: i64) -> Vec<u64> {
+    let above = self.neighbour_row(y - 1);
+    let below = self.neighbour_row(y + 1);
+    let centre = self.row(y);
+
+    let neighbours = [
+      self.west_of(&above, y - 1),
+      self.east_of(&above, y - 1),
+      self.west_of(centre, y),
+      self.east_of(centre, y),
+      self.west_of(&below, y + 1),
+      self.east_of(&below, y + 1),
+    ];
+
+    let last_bits = (self.width as usize + 1) % 64;
+    let last_mask = if last_bits == 0 {
+      !0
+    } else {
+      (1 << last_bits) - 1
+    };
+
+    (0..self.words_per_row)
+      .map(|i| {
+        let counts = count_neighbours([
+          above[i],
+          below[i],
+          neighbours[0][i],
+          neighbours[1][i],
+          neighbours[2][i],
+          neighbours[3][i],
+          neighbours[4][i],
+          neighbours[5][i],
+        ]);
+
+        let mut born = 0;
+        let mut survives = 0;
+        for (alive_neighbours, matches) in counts.iter().enumerate() {
+          if self.rule.born(alive_neighbours) {
+            born |= matches;
+          }
+          if self.rule.survives(alive_neighbours) {
+            survives |= matches;
+          }
+        }
+
+        let alive = centre[i];
+        let next = (!alive & born) | (alive & survives);
+        if i + 1 == self.words_per_row {
+          next & last_mask
+        } else {
+          next
+        }
+      })
+      .collect()
+  }
+}
+
+// Sums eight neighbour words with a tree of full adders, then returns
+// for every count from 0 to 8 the bits whose neighbour count it is
+fn count_neighbours(words: [u64; 8]) -> [u64; 9] {
+  let full_add = |a: u64, b: u64, c: u64| (a ^ b ^ c, (a & b) | (c & (a ^ b)));
+
+  let (ones_a, twos_a) = full_add(words[0], words[1], words[2]);
+  let (ones_b, twos_b) = full_add(words[3], words[4], words[5]);
+  let (ones_c, twos_c) = (words[6] ^ words[7], words[6] & words[7]);
+
+  let (ones, twos_d) = full_add(ones_a, ones_b, ones_c);
+  let (twos_e, fours_a) = full_add(twos_a, twos_b, twos_c);
+  let (twos, fours_b) = (twos_e ^ twos_d, twos_e & twos_d);
+  let (fours, eights) = (fours_a ^ fours_b, fours_a & fours_b);
+
+  let mut counts = [0; 9];
+  for (count, matches) in counts.iter_mut().enumerate() {
+    let bit = |word: u64, place: usize| if count & place != 0 { word } else { !word };
+    *matches = bit(ones, 1) & bit(twos, 2) & bit(fours, 4) & bit(eights, 8);
+  }
+  counts
+}
+
+impl Universe for BitWorld {
+  fn width(&self) -> i64 {
+    self.width
+  }
+
+  fn height(&self) -> i64 {
+    self.height
+  }
+
+  fn tick(&self) -> u64 {
+    self.tick
+  }
+
+  fn set_tick(&mut self, tick: u64) {
+    self.tick = tick;
+  }
+
+  fn rule(&self) -> Rule {
+    self.rule
+  }
+
+  fn _tick(&mut self) {
+    let rows = (self.height + 1) as usize;
+    self.cells = workers::map_rows(rows, self.threads, |y| self.next_row(y as i64));
+    self.tick += 1;
+  }
+
+  fn alive_at(&self, x: i64, y: i64) -> bool {
+    if x < 0 || y < 0 || x > self.width || y > self.height {
+      return false;
+    }
+    self.row(y)[x as usize / 64] & 1 << (x % 64) != 0
+  }
+
+  fn set_alive(&mut self, x: i64, y: i64, alive: bool) -> Result<(), WorldError> {
+    if x < 0 || y < 0 || x > self.width || y > self.height {
+      return Err(WorldError::OutOfBounds { x, y });
+    }
+    self.set_cell(x, y, alive);
+    Ok(())
+  }
+
+  fn render(&self) -> String {
+    let mut rendering = String::with_capacity(((self.width + 2) * (self.height + 1)) as usize);
+    for y in 0..=self.height {
+      for x in 0..=self.width {
+        rendering.push(if self.alive_at(x, y) { 'o' } else { ' ' });
+      }
+      rendering.push('\n');
+    }
+    rendering
+  }
+}
+
+impl From<&World> for BitWorld {
+  fn from(world: &World) -> BitWorld {
+    let settings = Settings {
+      rule: world.rule(),
+      topology: world.topology(),
+      threads: world.threads(),
+      ..Settings::default()
+    };
+
+    let mut bit_world = BitWorld::empty(world.width(), world.height(), settings)
+      .expect("the world already holds every location");
+    bit_world.tick = world.tick();
+    for y in 0..=world.height() {
+      for x in 0..=world.width() {
+        let alive = world.cell_at(x, y).is_some_and(|cell| cell.alive);
+        bit_world.set_cell(x, y, alive);
+      }
+    }
+    bit_world
+  }
+}
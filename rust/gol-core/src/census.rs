@@ -0,0 +1,481 @@
+use crate::rule::Rule;
+use crate::sparse_world::SparseWorld;
+use crate::universe::Universe;
+use crate::world::{Settings, CACHED_DIRECTIONS};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+// The digits of the extended Wechsler format, one per column of five
+// cells, with y and z kept back for runs of zeros and strip breaks
+const DIGITS: &[u8; 32] = b"0123456789abcdefghijklmnopqrstuv";
+const RUN_DIGITS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+// Rotations and reflections as matrices [a, b, c, d], which take (x, y)
+// to (ax + by, cx + dy)
+#[rustfmt::skip]
+const ORIENTATIONS: [[i64; 4]; 8] = [
+  [1, 0, 0, 1],  [-1, 0, 0, 1],  [1, 0, 0, -1],  [-1, 0, 0, -1],
+  [0, 1, 1, 0],  [0, -1, 1, 0],  [0, 1, -1, 0],  [0, -1, -1, 0],
+];
+
+// Objects are played on their own for this many generations to find
+// their period
+const MAX_PERIOD: u64 = 256;
+
+// Common objects in Conway's Life, by their apgcode
+const NAMES: [(&str, &str); 16] = [
+  ("xs4_33", "block"),
+  ("xs6_696", "beehive"),
+  ("xs7_2596", "loaf"),
+  ("xs5_253", "boat"),
+  ("xs4_252", "tub"),
+  ("xs6_356", "ship"),
+  ("xs8_6996", "pond"),
+  ("xs7_25ac", "long boat"),
+  ("xs6_25a4", "barge"),
+  ("xs8_35ac", "long ship"),
+  ("xp2_7", "blinker"),
+  ("xp2_7e", "toad"),
+  ("xp2_318c", "beacon"),
+  ("xp3_co9nas0san9oczgoldlo0oldlogz1047210127401", "pulsar"),
+  ("xq4_153", "glider"),
+  ("xq4_6frc", "lightweight spaceship"),
+];
+
+/// Which cells count as touching when a universe is split into
+/// objects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Connectivity {
+  /// Cells that share an edge, four per cell.
+  Orthogonal,
+  /// Cells that share an edge or a corner, the eight neighbours the
+  /// rules count. This is the default.
+  #[default]
+  Moore,
+}
+
+impl Connectivity {
+  fn directions(self) -> impl Iterator<Item = [i64; 2]> {
+    CACHED_DIRECTIONS
+      .into_iter()
+      .filter(move |[x, y]| self == Connectivity::Moore || *x == 0 || *y == 0)
+  }
+}
+
+/// A group of living cells that touch each other and no other cells,
+/// such as a block or a glider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Island {
+  /// Locations of the island's cells in the universe.
+  pub cells: Vec<(i64, i64)>,
+  /// The apgcode identifying the island, see [`apgcode`].
+  pub code: String,
+  /// Generations the island takes to come back, `None` when it didn't
+  /// within 256 generations.
+  pub period: Option<u64>,
+  /// How the island travels, for spaceships.
+  pub velocity: Option<Velocity>,
+}
+
+impl Island {
+  /// Returns the common name of the island, such as `block`, when it is
+  /// one of the well known objects of Conway's Life.
+  pub fn name(&self) -> Option<&'static str> {
+    object_name(&self.code)
+  }
+
+  // Plays the cells on their own to find out what they are
+  pub(crate) fn new(cells: Vec<(i64, i64)>, rule: Rule) -> Island {
+    let evolution = evolve(&cells, rule);
+    Island {
+      code: code(&cells, evolution.as_ref()),
+      period: evolution.as_ref().map(|evolution| evolution.period),
+      velocity: evolution.and_then(|evolution| evolution.velocity()),
+      cells,
+    }
+  }
+
+  /// Returns the top left corner of the island's bounding box.
+  pub fn position(&self) -> (i64, i64) {
+    let x = self.cells.iter().map(|&(x, _)| x).min().unwrap_or(0);
+    let y = self.cells.iter().map(|&(_, y)| y).min().unwrap_or(0);
+    (x, y)
+  }
+}
+
+/// How far a spaceship moves each period, with `y` growing downwards
+/// the way rows are numbered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Velocity {
+  /// Columns moved each period, positive to the east.
+  pub dx: i64,
+  /// Rows moved each period, positive to the south.
+  pub dy: i64,
+  /// Generations in each period.
+  pub period: u64,
+}
+
+impl Velocity {
+  /// Returns the speed in terms of `c`, one cell per generation, such as
+  /// `c/4` for a glider or `2c/5` for a copperhead. Oblique ships that
+  /// move along both axes by different amounts get the amounts first,
+  /// as in `(2,1)c/6`.
+  pub fn speed(&self) -> String {
+    let (dx, dy) = (self.dx.unsigned_abs(), self.dy.unsigned_abs());
+    if self.direction() == "oblique" {
+      return format!("({},{})c/{}", dx.max(dy), dx.min(dy), self.period);
+    }
+
+    let cells = dx.max(dy);
+    let divisor = gcd(cells, self.period);
+    let (cells, period) = (cells / divisor, self.period / divisor);
+    match (cells, period) {
+      (1, 1) => "c".to_string(),
+      (1, period) => format!("c/{}", period),
+      (cells, 1) => format!("{}c", cells),
+      (cells, period) => format!("{}c/{}", cells, period),
+    }
+  }
+
+  /// Returns `orthogonal`, `diagonal` or `oblique`.
+  pub fn direction(&self) -> &'static str {
+    if self.dx == 0 || self.dy == 0 {
+      "orthogonal"
+    } else if self.dx.abs() == self.dy.abs() {
+      "diagonal"
+    } else {
+      "oblique"
+    }
+  }
+
+  /// Returns the compass heading, such as `south-east`, to the nearest
+  /// of the eight points for oblique ships.
+  pub fn heading(&self) -> &'static str {
+    // Oblique ships head along the axis they move furthest on, unless
+    // the other is at least half as far
+    let (dx, dy) = (self.dx.abs(), self.dy.abs());
+    let east_west = if 2 * dx >= dy { self.dx.signum() } else { 0 };
+    let north_south = if 2 * dy >= dx { self.dy.signum() } else { 0 };
+    match (east_west, north_south) {
+      (0, -1) => "north",
+      (1, -1) => "north-east",
+      (1, 0) => "east",
+      (1, 1) => "south-east",
+      (0, 1) => "south",
+      (-1, 1) => "south-west",
+      (-1, 0) => "west",
+      (-1, -1) => "north-west",
+      _ => "nowhere",
+    }
+  }
+}
+
+impl fmt::Display for Velocity {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "{} {} heading {}",
+      self.speed(),
+      self.direction(),
+      self.heading()
+    )
+  }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+  if b == 0 {
+    a
+  } else {
+    gcd(b, a % b)
+  }
+}
+
+/// Every island in a universe, identified, for a summary of what a soup
+/// settled into.
+///
+/// It prints as counts of each kind of object, most common first, by
+/// name where the object has one and by apgcode otherwise:
+///
+/// ```text
+/// block ×12, blinker ×5, glider ×2, xs16_g88m996zw3 ×1
+/// ```
+///
+/// Islands are split by where the cells are, so objects that wrap
+/// around the edges of a torus count as two, and objects close enough
+/// to touch, such as a blinker next to a block, count as one. Objects
+/// pressed against the edge of a bounded world can be stable only
+/// because nothing is born past the edge, and come out as `zz` codes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Census {
+  /// Generation of the universe the census was taken at.
+  pub generation: u64,
+  /// Every island, in no particular order.
+  pub islands: Vec<Island>,
+}
+
+impl Census {
+  /// Splits the living cells of a universe into islands and identifies
+  /// each of them under the universe's rule.
+  pub fn new(universe: &dyn Universe, connectivity: Connectivity) -> Census {
+    let rule = universe.rule();
+    Census {
+      generation: universe.tick(),
+      islands: islands(&universe.live_cells(), connectivity)
+        .into_iter()
+        .map(|cells| Island::new(cells, rule))
+        .collect(),
+    }
+  }
+
+  /// Returns the islands that travel, such as gliders leaving a soup.
+  pub fn spaceships(&self) -> impl Iterator<Item = &Island> {
+    self
+      .islands
+      .iter()
+      .filter(|island| island.velocity.is_some())
+  }
+
+  /// Returns how many islands there are of each kind, by name or
+  /// apgcode, most common first and alphabetically after that.
+  pub fn counts(&self) -> Vec<(String, usize)> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for island in &self.islands {
+      let name = island.name().map_or(island.code.clone(), str::to_string);
+      *counts.entry(name).or_default() += 1;
+    }
+
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+  }
+}
+
+impl fmt::Display for Census {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let counts = self.counts();
+    if counts.is_empty() {
+      return write!(f, "nothing");
+    }
+
+    for (i, (name, count)) in counts.iter().enumerate() {
+      if i > 0 {
+        write!(f, ", ")?;
+      }
+      write!(f, "{} ×{}", name, count)?;
+    }
+    Ok(())
+  }
+}
+
+/// Splits cells into islands of cells that touch, walking outwards from
+/// each cell that isn't in an island yet.
+pub fn islands(cells: &[(i64, i64)], connectivity: Connectivity) -> Vec<Vec<(i64, i64)>> {
+  let mut unvisited: HashSet<(i64, i64)> = cells.iter().copied().collect();
+  let mut islands = Vec::new();
+
+  for &cell in cells {
+    if !unvisited.remove(&cell) {
+      continue;
+    }
+
+    let mut island = vec![cell];
+    let mut next = 0;
+    while next < island.len() {
+      let (x, y) = island[next];
+      next += 1;
+      for [rel_x, rel_y] in connectivity.directions() {
+        let neighbour = (x.wrapping_add(rel_x), y.wrapping_add(rel_y));
+        if unvisited.remove(&neighbour) {
+          island.push(neighbour);
+        }
+      }
+    }
+    islands.push(island);
+  }
+  islands
+}
+
+/// Returns the apgcode of an object, the name Catagolue and apgsearch
+/// give it, which is the same for every phase, rotation and reflection.
+///
+/// The object is played on its own to find its period. Still lifes are
+/// `xs` and their population, oscillators `xp` and spaceships `xq`
+/// followed by the period. Then comes the object in the extended
+/// Wechsler format, for whichever phase and orientation gives the
+/// shortest code, or the first of those alphabetically:
+///
+/// ```text
+/// xs4_33      block
+/// xp2_7       blinker
+/// xq4_153     glider
+/// ```
+///
+/// Objects that don't repeat within 256 generations, or that die out,
+/// are `zz` followed by the code of the phase given.
+pub fn apgcode(cells: &[(i64, i64)], rule: Rule) -> String {
+  code(cells, evolve(cells, rule).as_ref())
+}
+
+/// Returns the common name of an object by its apgcode, such as
+/// `glider` for `xq4_153`, when it is one of the well known objects of
+/// Conway's Life.
+pub fn object_name(apgcode: &str) -> Option<&'static str> {
+  NAMES
+    .iter()
+    .find(|(code, _)| *code == apgcode)
+    .map(|&(_, name)| name)
+}
+
+fn code(cells: &[(i64, i64)], evolution: Option<&Evolution>) -> String {
+  match evolution {
+    Some(evolution) => {
+      let prefix = match (evolution.period, evolution.displacement) {
+        (1, _) => format!("xs{}", cells.len()),
+        (period, (0, 0)) => format!("xp{}", period),
+        (period, _) => format!("xq{}", period),
+      };
+      let code = evolution
+        .phases
+        .iter()
+        .map(|phase| canonical_wechsler(phase))
+        .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+        .unwrap_or_default();
+      format!("{}_{}", prefix, code)
+    }
+    None => format!("zz_{}", canonical_wechsler(cells)),
+  }
+}
+
+// How an object plays out on its own until it comes back
+struct Evolution {
+  period: u64,
+  // How far the object moved in one period
+  displacement: (i64, i64),
+  // Every phase, from the one given
+  phases: Vec<Vec<(i64, i64)>>,
+}
+
+impl Evolution {
+  fn velocity(&self) -> Option<Velocity> {
+    let (dx, dy) = self.displacement;
+    (self.displacement != (0, 0)).then_some(Velocity {
+      dx,
+      dy,
+      period: self.period,
+    })
+  }
+}
+
+// Plays cells on an unbounded plane until they repeat, maybe somewhere
+// else. Empty objects and rules that SparseWorld can't play never do
+fn evolve(cells: &[(i64, i64)], rule: Rule) -> Option<Evolution> {
+  if cells.is_empty() {
+    return None;
+  }
+
+  let settings = Settings {
+    rule,
+    density: 0.0,
+    ..Settings::default()
+  };
+  let mut world = SparseWorld::with_settings(0, 0, settings).ok()?;
+  for &(x, y) in cells {
+    world.set_alive(x, y, true);
+  }
+
+  let (start, origin) = normalise(cells.to_vec());
+  let mut phases = vec![cells.to_vec()];
+  for period in 1..=MAX_PERIOD {
+    world._tick();
+    let phase: Vec<(i64, i64)> = world.live_cells().collect();
+    if phase.len() == cells.len() {
+      let (shape, corner) = normalise(phase.clone());
+      if shape == start {
+        return Some(Evolution {
+          period,
+          displacement: (corner.0 - origin.0, corner.1 - origin.1),
+          phases,
+        });
+      }
+    }
+    if phase.is_empty() {
+      return None;
+    }
+    phases.push(phase);
+  }
+  None
+}
+
+// Moves cells so the top left of their bounding box is (0, 0) and
+// sorts them, returning where that corner was
+fn normalise(mut cells: Vec<(i64, i64)>) -> (Vec<(i64, i64)>, (i64, i64)) {
+  let min_x = cells.iter().map(|&(x, _)| x).min().unwrap_or(0);
+  let min_y = cells.iter().map(|&(_, y)| y).min().unwrap_or(0);
+  for cell in &mut cells {
+    *cell = (cell.0 - min_x, cell.1 - min_y);
+  }
+  cells.sort_unstable();
+  (cells, (min_x, min_y))
+}
+
+// The shortest code of all eight rotations and reflections, the first
+// alphabetically when several are as short
+fn canonical_wechsler(cells: &[(i64, i64)]) -> String {
+  ORIENTATIONS
+    .iter()
+    .map(|[a, b, c, d]| {
+      let oriented = cells.iter().map(|&(x, y)| (a * x + b * y, c * x + d * y));
+      let (cells, _) = normalise(oriented.collect());
+      wechsler(&cells)
+    })
+    .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+    .unwrap_or_default()
+}
+
+// Cells from (0, 0) are cut into strips five rows tall. Each column of
+// a strip is one digit, with the top row as its lowest bit, trailing
+// zeros are dropped and strips are joined with z. Runs of zeros then
+// shrink to w for two, x for three and y plus a digit for four to 39
+fn wechsler(cells: &[(i64, i64)]) -> String {
+  let width = cells.iter().map(|&(x, _)| x + 1).max().unwrap_or(0);
+  let height = cells.iter().map(|&(_, y)| y + 1).max().unwrap_or(0);
+  let cells: HashSet<&(i64, i64)> = cells.iter().collect();
+
+  let mut strips = Vec::new();
+  for top in (0..height).step_by(5) {
+    let mut strip = String::new();
+    for x in 0..width {
+      let digit = (0..5)
+        .filter(|&row| cells.contains(&(x, top + row)))
+        .fold(0, |digit, row| digit | 1 << row);
+      strip.push(DIGITS[digit] as char);
+    }
+    strips.push(strip.trim_end_matches('0').to_string());
+  }
+
+  let mut code = String::new();
+  let mut zeros = 0;
+  for char in strips.join("z").chars().chain(Some('$')) {
+    if char == '0' {
+      zeros += 1;
+      continue;
+    }
+    while zeros > 0 {
+      let run = zeros.min(39);
+      match run {
+        1 => code.push('0'),
+        2 => code.push('w'),
+        3 => code.push('x'),
+        _ => {
+          code.push('y');
+          code.push(RUN_DIGITS[run - 4] as char);
+        }
+      }
+      zeros -= run;
+    }
+    if char != '$' {
+      code.push(char);
+    }
+  }
+  code
+}
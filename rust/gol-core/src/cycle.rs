@@ -0,0 +1,101 @@
+use crate::random::Random;
+use crate::universe::Universe;
+use std::collections::HashMap;
+
+/// A universe that has started repeating itself, found by
+/// [`CycleDetector`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cycle {
+  /// The first generation of the cycle, the first one that comes back.
+  pub start: u64,
+  /// How many generations it takes to come back, `1` for still lifes.
+  pub period: u64,
+}
+
+impl Cycle {
+  /// Returns whether nothing changes any more, or nothing is alive.
+  pub fn is_still(&self) -> bool {
+    self.period == 1
+  }
+}
+
+/// Watches a universe generation by generation and notices when it has
+/// become periodic, such as a soup that settled into still lifes and
+/// blinkers.
+///
+/// Every generation's living cells are hashed into 64 bits, so only the
+/// hashes are kept, and the first generation to repeat an earlier hash
+/// closes the cycle. Two different generations can share a hash, but
+/// with 64 bits that is too unlikely to matter. Patterns that travel,
+/// such as gliders on an unbounded backend, never repeat in place and
+/// so never close a cycle.
+#[derive(Debug, Clone, Default)]
+pub struct CycleDetector {
+  seen: HashMap<u64, u64>,
+  cycle: Option<Cycle>,
+}
+
+impl CycleDetector {
+  /// Creates a detector that hasn't seen any generations.
+  pub fn new() -> CycleDetector {
+    CycleDetector::default()
+  }
+
+  /// Records the universe's current generation, and returns the cycle
+  /// once one is found. After that the same cycle is returned without
+  /// looking at the universe again.
+  pub fn observe(&mut self, universe: &dyn Universe) -> Option<Cycle> {
+    if self.cycle.is_some() {
+      return self.cycle;
+    }
+
+    let generation = universe.tick();
+    let hash = CycleDetector::hash(universe);
+    match self.seen.get(&hash) {
+      Some(&start) if start < generation => {
+        self.cycle = Some(Cycle {
+          start,
+          period: generation - start,
+        });
+      }
+      Some(_) => {}
+      None => {
+        self.seen.insert(hash, generation);
+      }
+    }
+    self.cycle
+  }
+
+  /// Returns the cycle found so far, if any.
+  pub fn cycle(&self) -> Option<Cycle> {
+    self.cycle
+  }
+
+  /// Plays the universe until it repeats itself or `generations` more
+  /// generations have been played, and returns the cycle if there was
+  /// one.
+  pub fn run(&mut self, universe: &mut dyn Universe, generations: u64) -> Option<Cycle> {
+    let end = universe.tick().saturating_add(generations);
+    while self.observe(universe).is_none() && universe.tick() < end {
+      universe._tick();
+    }
+    self.observe(universe)
+  }
+
+  // Each cell is mixed into 64 random looking bits, and those are added
+  // up, so the order live_cells returns them in doesn't matter, and
+  // cells can be taken out again by subtracting theirs
+  pub(crate) fn hash(universe: &dyn Universe) -> u64 {
+    CycleDetector::hash_cells(&universe.live_cells())
+  }
+
+  pub(crate) fn hash_cells(cells: &[(i64, i64)]) -> u64 {
+    cells
+      .iter()
+      .map(|&(x, y)| {
+        let x = Random::new(x as u64).next_u64();
+        Random::new(x ^ y as u64).next_u64()
+      })
+      .fold(0, u64::wrapping_add)
+  }
+}
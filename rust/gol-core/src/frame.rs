@@ -0,0 +1,98 @@
+use crate::pattern::Pattern;
+use crate::world::WorldError;
+
+impl Pattern {
+  /// Parses a frame as `render` prints it in every implementation, `o`
+  /// for alive and space for dead, one line per row.
+  ///
+  /// The `#N - World tick took...` line `Play` prints above a frame
+  /// gives the generation. When a capture holds several frames, the one
+  /// after the last of those lines is read, and the escape codes that
+  /// clear the screen between them are skipped. Rows that lost their
+  /// trailing spaces are still as wide as the widest row, but empty
+  /// rows at the bottom can't be told apart from the end of the frame:
+  ///
+  /// ```text
+  /// #3 - World tick took 0.051 (0.049) - Rendering took 0.009 (0.010)
+  ///  o
+  ///   o
+  /// ooo
+  /// ```
+  pub fn from_render(text: &str) -> Result<Pattern, WorldError> {
+    let text = strip_escape_codes(text);
+    let lines: Vec<(usize, &str)> = text
+      .lines()
+      .enumerate()
+      .map(|(i, line)| (i + 1, line.trim_end_matches('\r')))
+      .collect();
+
+    let mut pattern = Pattern::default();
+    let frame = match lines.iter().rposition(|(_, line)| line.starts_with('#')) {
+      Some(header) => {
+        pattern.generation = read_header(lines[header])?;
+        &lines[header + 1..]
+      }
+      None => &lines[..],
+    };
+
+    // Play prints an empty line after each frame, which isn't a row
+    let rows = frame
+      .iter()
+      .rposition(|(_, line)| !line.is_empty())
+      .map_or(0, |last| last + 1);
+    if rows == 0 {
+      return Err(WorldError::InvalidPattern {
+        line: lines.len().max(1),
+        reason: "the frame has no rows".to_string(),
+      });
+    }
+
+    for (y, &(number, line)) in frame[..rows].iter().enumerate() {
+      for (x, char) in line.chars().enumerate() {
+        match char {
+          'o' => pattern.cells.push((x as i64, y as i64)),
+          ' ' => {}
+          char => {
+            return Err(WorldError::InvalidPattern {
+              line: number,
+              reason: format!("unexpected character {:?}", char),
+            })
+          }
+        }
+      }
+      pattern.columns = pattern.columns.max(line.chars().count() as i64);
+    }
+    pattern.rows = rows as i64;
+    Ok(pattern)
+  }
+}
+
+// Reads the generation from `#12 - World tick took...`
+fn read_header((number, line): (usize, &str)) -> Result<u64, WorldError> {
+  let generation = line[1..].split(' ').next().unwrap_or_default();
+  generation.parse().map_err(|_| WorldError::UnknownFormat {
+    line: number,
+    header: line.to_string(),
+  })
+}
+
+// Drops CSI sequences such as `ESC [ H` and `ESC [ 2 J`, which end at
+// the first character from `@` to `~`
+fn strip_escape_codes(text: &str) -> String {
+  let mut stripped = String::with_capacity(text.len());
+  let mut chars = text.chars();
+  while let Some(char) = chars.next() {
+    if char == '\u{1b}' {
+      if chars.next() == Some('[') {
+        for char in chars.by_ref() {
+          if ('@'..='~').contains(&char) {
+            break;
+          }
+        }
+      }
+      continue;
+    }
+    stripped.push(char);
+  }
+  stripped
+}
@@ -0,0 +1,683 @@
+use crate::random::Random;
+use crate::rule::Rule;
+use crate::universe::{BoundingBox, Universe};
+use crate::world::{Settings, World, WorldError};
+use std::collections::HashMap;
+use std::mem;
+
+mod macrocell;
+
+type NodeId = u32;
+
+// Leaves are the two level 0 nodes, every other node is a square of
+// four children one level down, stored once no matter how often it
+// appears in the universe
+#[derive(Clone, Copy)]
+struct Node {
+  level: u8,
+  nw: NodeId,
+  ne: NodeId,
+  sw: NodeId,
+  se: NodeId,
+  population: u64,
+}
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+// A root this deep spans the whole i64 plane around the origin, which is
+// as far as cells can be handed out to the rest of the crate
+const MAX_LEVEL: u8 = 64;
+
+/// A universe stored as a canonicalised quadtree, which memoises how
+/// each square evolves and so can jump `2^k` generations at once.
+///
+/// The universe is an unbounded plane, `width` and `height` only choose
+/// the size of the viewport that is rendered, which starts at `(0, 0)`
+/// unless it is moved. Nodes
+/// are garbage collected between steps once their estimated memory use
+/// passes the memory limit.
+pub struct HashLife {
+  viewport_x: i64,
+  viewport_y: i64,
+  width: i64,
+  height: i64,
+  tick: u64,
+  rule: Rule,
+
+  nodes: Vec<Node>,
+  index: HashMap<[NodeId; 4], NodeId>,
+  empties: Vec<NodeId>,
+  results: HashMap<(NodeId, u8), NodeId>,
+  root: NodeId,
+  memory_limit: usize,
+}
+
+impl HashLife {
+  /// Memory the node cache may use before it is garbage collected,
+  /// unless changed with [`HashLife::set_memory_limit`].
+  pub const DEFAULT_MEMORY_LIMIT: usize = 256 * 1024 * 1024;
+
+  /// Creates a Conway universe with a random soup of cells in the
+  /// viewport.
+  pub fn new(width: i64, height: i64) -> HashLife {
+    HashLife::with_settings(width, height, Settings::default())
+      .expect("the default settings are supported")
+  }
+
+  /// Creates a universe with the given settings and a random soup of
+  /// cells in the viewport, the same soup [`World`] gets from those
+  /// settings. The plane has no edges, so only
+  /// [`Topology::Bounded`](crate::Topology::Bounded) is accepted, and
+  /// rules where cells are born without neighbours (`B0`) can't be
+  /// played on it. Each generation is played on the calling thread.
+  pub fn with_settings(
+    width: i64,
+    height: i64,
+    settings: Settings,
+  ) -> Result<HashLife, WorldError> {
+    settings.check_unbounded()?;
+
+    let mut hashlife = HashLife::empty(width, height, settings.rule);
+    hashlife.populate_cells(settings.soup_seed(), settings.density);
+    Ok(hashlife)
+  }
+
+  fn empty(width: i64, height: i64, rule: Rule) -> HashLife {
+    let leaf = |population| Node {
+      level: 0,
+      nw: DEAD,
+      ne: DEAD,
+      sw: DEAD,
+      se: DEAD,
+      population,
+    };
+
+    let mut hashlife = HashLife {
+      viewport_x: 0,
+      viewport_y: 0,
+      width,
+      height,
+      tick: 0,
+      rule,
+      nodes: vec![leaf(0), leaf(1)],
+      index: HashMap::new(),
+      empties: vec![DEAD],
+      results: HashMap::new(),
+      root: DEAD,
+      memory_limit: HashLife::DEFAULT_MEMORY_LIMIT,
+    };
+    hashlife.root = hashlife.empty_node(3);
+    hashlife
+  }
+
+  fn populate_cells(&mut self, seed: u64, density: f64) {
+    let mut random = Random::new(seed);
+
+    for y in 0..=self.height {
+      for x in 0..=self.width {
+        let alive = random.next_alive(density);
+        self.set_alive(x, y, alive);
+      }
+    }
+  }
+
+  /// Returns the birth and survival rule of the universe.
+  pub fn rule(&self) -> Rule {
+    self.rule
+  }
+
+  /// Changes the rule played from the next step on. Rules where cells
+  /// are born without neighbours (`B0`) can't be played on the plane.
+  pub fn set_rule(&mut self, rule: Rule) -> Result<(), WorldError> {
+    if rule.born(0) {
+      return Err(WorldError::UnsupportedRule { rule });
+    }
+    self.rule = rule;
+    // Memoised results were worked out under the old rule
+    self.results.clear();
+    Ok(())
+  }
+
+  /// Returns the top left corner of the viewport.
+  pub fn viewport(&self) -> (i64, i64) {
+    (self.viewport_x, self.viewport_y)
+  }
+
+  /// Moves and resizes the viewport that `render` draws.
+  pub fn set_viewport(&mut self, x: i64, y: i64, width: i64, height: i64) {
+    self.viewport_x = x;
+    self.viewport_y = y;
+    self.width = width;
+    self.height = height;
+  }
+
+  /// Returns the number of living cells in the whole universe.
+  pub fn population(&self) -> u64 {
+    self.nodes[self.root as usize].population
+  }
+
+  /// Returns the box around every living cell, or `None` when the
+  /// universe is empty.
+  pub fn bounding_box(&self) -> Option<BoundingBox> {
+    let [min_x, min_y, max_x, max_y] = self.box_in(self.root, &mut HashMap::new())?;
+    // Loading and stepping both keep the root within MAX_LEVEL
+    let half = self.half();
+    let at = |value: i128| i64::try_from(value - half).expect("cells have i64 locations");
+    Some(BoundingBox {
+      min_x: at(min_x),
+      min_y: at(min_y),
+      max_x: at(max_x),
+      max_y: at(max_y),
+    })
+  }
+
+  /// Returns the number of distinct nodes in the cache.
+  pub fn node_count(&self) -> usize {
+    self.nodes.len()
+  }
+
+  /// Returns a rough estimate, in bytes, of the memory the node and
+  /// result caches use.
+  pub fn memory_usage(&self) -> usize {
+    let node = mem::size_of::<Node>() + mem::size_of::<([NodeId; 4], NodeId)>();
+    let result = mem::size_of::<((NodeId, u8), NodeId)>();
+
+    // HashMaps keep roughly one control byte and some slack per entry
+    self.nodes.len() * (node + 8) + self.results.len() * (result + 8)
+  }
+
+  /// Returns the memory, in bytes, the caches may use before nodes are
+  /// garbage collected.
+  pub fn memory_limit(&self) -> usize {
+    self.memory_limit
+  }
+
+  /// Sets the memory, in bytes, the caches may use before nodes are
+  /// garbage collected. The limit is checked between steps.
+  pub fn set_memory_limit(&mut self, bytes: usize) {
+    self.memory_limit = bytes;
+  }
+
+  /// Sets the cell at a location, growing the universe if needed.
+  pub fn set_alive(&mut self, x: i64, y: i64, alive: bool) {
+    while !self.contains(x, y) {
+      self.expand();
+    }
+
+    let half = self.half();
+    self.root = self.set_in(self.root, x as i128 + half, y as i128 + half, alive);
+  }
+
+  /// Advances the universe by `2^k` generations at once, or fails
+  /// without changing it when that would take the generation count past
+  /// `u64::MAX` or cells past the `i64` coordinates of the plane. The
+  /// memory limit is only checked before and after the step,
+  /// so a large step can go past it on the way.
+  pub fn step_pow2(&mut self, k: u8) -> Result<(), WorldError> {
+    let tick = 1u64
+      .checked_shl(k as u32)
+      .and_then(|generations| self.tick.checked_add(generations))
+      .ok_or(WorldError::TooManyGenerations { tick: self.tick })?;
+
+    if self.memory_usage() > self.memory_limit {
+      self.collect_garbage();
+    }
+
+    // Live cells must sit in the middle quarter of the root, and one
+    // more doubling leaves a margin around them in the half that step
+    // returns, wide enough that nothing escapes in 2^k generations
+    while self.level() < k as usize + 3 || !self.padded() {
+      self.expand();
+    }
+    self.expand();
+    let mut result = self.step(self.root, k);
+
+    // Every cell has to keep an i64 location, so a result deeper than
+    // that is only kept when its outer ring is empty
+    while self.nodes[result as usize].level > MAX_LEVEL {
+      let centre = self.centre(result);
+      if self.nodes[centre as usize].population != self.nodes[result as usize].population {
+        return Err(WorldError::TooManyGenerations { tick: self.tick });
+      }
+      result = centre;
+    }
+    self.root = result;
+    self.tick = tick;
+
+    if self.memory_usage() > self.memory_limit {
+      self.collect_garbage();
+    }
+    Ok(())
+  }
+
+  /// Advances the universe by any number of generations, as a series
+  /// of power of two jumps, or fails without changing it when that would
+  /// take the generation count past `u64::MAX`. Jumps that would take
+  /// cells past the `i64` coordinates of the plane fail the same way,
+  /// once the jumps before them have been played.
+  pub fn advance(&mut self, generations: u64) -> Result<(), WorldError> {
+    if self.tick.checked_add(generations).is_none() {
+      return Err(WorldError::TooManyGenerations { tick: self.tick });
+    }
+    // The largest jump goes first, as it is the one most likely to fail
+    for k in (0..64).rev() {
+      if generations & 1 << k != 0 {
+        self.step_pow2(k)?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Rebuilds the node cache with only the nodes the universe still
+  /// uses, and forgets all memoised results.
+  pub fn collect_garbage(&mut self) {
+    let leaves = vec![self.nodes[DEAD as usize], self.nodes[ALIVE as usize]];
+    let old_nodes = mem::replace(&mut self.nodes, leaves);
+    self.index.clear();
+    self.results.clear();
+    self.empties = vec![DEAD];
+
+    let mut copied = HashMap::new();
+    self.root = self.copy_node(&old_nodes, self.root, &mut copied);
+  }
+
+  fn copy_node(
+    &mut self,
+    old_nodes: &[Node],
+    id: NodeId,
+    copied: &mut HashMap<NodeId, NodeId>,
+  ) -> NodeId {
+    if id == DEAD || id == ALIVE {
+      return id;
+    }
+    if let Some(&new_id) = copied.get(&id) {
+      return new_id;
+    }
+
+    let node = old_nodes[id as usize];
+    let nw = self.copy_node(old_nodes, node.nw, copied);
+    let ne = self.copy_node(old_nodes, node.ne, copied);
+    let sw = self.copy_node(old_nodes, node.sw, copied);
+    let se = self.copy_node(old_nodes, node.se, copied);
+    let new_id = self.join(nw, ne, sw, se);
+    copied.insert(id, new_id);
+    new_id
+  }
+
+  fn level(&self) -> usize {
+    self.nodes[self.root as usize].level as usize
+  }
+
+  // The root spans -half to half - 1 on both axes
+  fn half(&self) -> i128 {
+    1 << (self.level() - 1)
+  }
+
+  fn contains(&self, x: i64, y: i64) -> bool {
+    let half = self.half();
+    (-half..half).contains(&(x as i128)) && (-half..half).contains(&(y as i128))
+  }
+
+  fn join(&mut self, nw: NodeId, ne: NodeId, sw: NodeId, se: NodeId) -> NodeId {
+    if let Some(&id) = self.index.get(&[nw, ne, sw, se]) {
+      return id;
+    }
+
+    let population = [nw, ne, sw, se].iter().fold(0u64, |total, &child| {
+      total.saturating_add(self.nodes[child as usize].population)
+    });
+    let id = self.nodes.len() as NodeId;
+    self.nodes.push(Node {
+      level: self.nodes[nw as usize].level + 1,
+      nw,
+      ne,
+      sw,
+      se,
+      population,
+    });
+    self.index.insert([nw, ne, sw, se], id);
+    id
+  }
+
+  fn empty_node(&mut self, level: usize) -> NodeId {
+    while self.empties.len() <= level {
+      let empty = self.empties[self.empties.len() - 1];
+      let bigger = self.join(empty, empty, empty, empty);
+      self.empties.push(bigger);
+    }
+    self.empties[level]
+  }
+
+  // Doubles the size of the root, keeping the universe centred
+  fn expand(&mut self) {
+    let root = self.nodes[self.root as usize];
+    let empty = self.empty_node(root.level as usize - 1);
+
+    let nw = self.join(empty, empty, empty, root.nw);
+    let ne = self.join(empty, empty, root.ne, empty);
+    let sw = self.join(empty, root.sw, empty, empty);
+    let se = self.join(root.se, empty, empty, empty);
+    self.root = self.join(nw, ne, sw, se);
+  }
+
+  fn padded(&self) -> bool {
+    let root = self.nodes[self.root as usize];
+    let inner = [
+      self.nodes[root.nw as usize].se,
+      self.nodes[root.ne as usize].sw,
+      self.nodes[root.sw as usize].ne,
+      self.nodes[root.se as usize].nw,
+    ];
+
+    let inner_population = inner
+      .iter()
+      .map(|&id| self.nodes[id as usize].population)
+      .fold(0u64, u64::saturating_add);
+    inner_population == root.population
+  }
+
+  fn set_in(&mut self, id: NodeId, x: i128, y: i128, alive: bool) -> NodeId {
+    let node = self.nodes[id as usize];
+    if node.level == 0 {
+      return if alive { ALIVE } else { DEAD };
+    }
+
+    let half = 1 << (node.level - 1);
+    let (mut nw, mut ne, mut sw, mut se) = (node.nw, node.ne, node.sw, node.se);
+    match (x < half, y < half) {
+      (true, true) => nw = self.set_in(nw, x, y, alive),
+      (false, true) => ne = self.set_in(ne, x - half, y, alive),
+      (true, false) => sw = self.set_in(sw, x, y - half, alive),
+      (false, false) => se = self.set_in(se, x - half, y - half, alive),
+    }
+    self.join(nw, ne, sw, se)
+  }
+
+  fn alive_in(&self, id: NodeId, x: i128, y: i128) -> bool {
+    let node = self.nodes[id as usize];
+    if node.population == 0 {
+      return false;
+    }
+    if node.level == 0 {
+      return true;
+    }
+
+    let half = 1 << (node.level - 1);
+    match (x < half, y < half) {
+      (true, true) => self.alive_in(node.nw, x, y),
+      (false, true) => self.alive_in(node.ne, x - half, y),
+      (true, false) => self.alive_in(node.sw, x, y - half),
+      (false, false) => self.alive_in(node.se, x - half, y - half),
+    }
+  }
+
+  // The box around a node's living cells, from its top left corner.
+  // Nodes repeat all over the tree, so each is only measured once
+  fn box_in(
+    &self,
+    id: NodeId,
+    boxes: &mut HashMap<NodeId, Option<[i128; 4]>>,
+  ) -> Option<[i128; 4]> {
+    let node = self.nodes[id as usize];
+    if node.population == 0 {
+      return None;
+    }
+    if node.level == 0 {
+      return Some([0; 4]);
+    }
+    if let Some(&bounds) = boxes.get(&id) {
+      return bounds;
+    }
+
+    let half = 1 << (node.level - 1);
+    let children = [
+      (node.nw, 0, 0),
+      (node.ne, half, 0),
+      (node.sw, 0, half),
+      (node.se, half, half),
+    ];
+    let mut bounds: Option<[i128; 4]> = None;
+    for (child, x, y) in children {
+      if let Some([min_x, min_y, max_x, max_y]) = self.box_in(child, boxes) {
+        let [min_x, min_y, max_x, max_y] = [min_x + x, min_y + y, max_x + x, max_y + y];
+        bounds = Some(match bounds {
+          Some(bounds) => [
+            bounds[0].min(min_x),
+            bounds[1].min(min_y),
+            bounds[2].max(max_x),
+            bounds[3].max(max_y),
+          ],
+          None => [min_x, min_y, max_x, max_y],
+        });
+      }
+    }
+
+    boxes.insert(id, bounds);
+    bounds
+  }
+
+  // Walks down to every living leaf, skipping empty nodes whole
+  fn collect_cells(&self, id: NodeId, x: i128, y: i128, cells: &mut Vec<(i64, i64)>) {
+    let node = self.nodes[id as usize];
+    if node.population == 0 {
+      return;
+    }
+    // The root never grows past MAX_LEVEL, so the casts can't wrap
+    if node.level == 0 {
+      cells.push((x as i64, y as i64));
+      return;
+    }
+
+    let half = 1 << (node.level - 1);
+    self.collect_cells(node.nw, x, y, cells);
+    self.collect_cells(node.ne, x + half, y, cells);
+    self.collect_cells(node.sw, x, y + half, cells);
+    self.collect_cells(node.se, x + half, y + half, cells);
+  }
+
+  // The node one level down made of the four innermost grandchildren
+  fn centre(&mut self, id: NodeId) -> NodeId {
+    let node = self.nodes[id as usize];
+    let nodes = &self.nodes;
+    self.join(
+      nodes[node.nw as usize].se,
+      nodes[node.ne as usize].sw,
+      nodes[node.sw as usize].ne,
+      nodes[node.se as usize].nw,
+    )
+  }
+
+  // The node straddling the border of two side by side nodes
+  fn horizontal(&mut self, west: NodeId, east: NodeId) -> NodeId {
+    let (west, east) = (self.nodes[west as usize], self.nodes[east as usize]);
+    self.join(west.ne, east.nw, west.se, east.sw)
+  }
+
+  // The node straddling the border of two stacked nodes
+  fn vertical(&mut self, north: NodeId, south: NodeId) -> NodeId {
+    let (north, south) = (self.nodes[north as usize], self.nodes[south as usize]);
+    self.join(north.sw, north.se, south.nw, south.ne)
+  }
+
+  // Returns the centre of a node, one level down, `2^k` generations
+  // later. `k` may be at most the node's level minus two
+  fn step(&mut self, id: NodeId, k: u8) -> NodeId {
+    let node = self.nodes[id as usize];
+    if node.population == 0 {
+      return self.empty_node(node.level as usize - 1);
+    }
+    if let Some(&result) = self.results.get(&(id, k)) {
+      return result;
+    }
+
+    let result = if node.level == 2 {
+      self.step_leaves(id)
+    } else {
+      let n00 = node.nw;
+      let n01 = self.horizontal(node.nw, node.ne);
+      let n02 = node.ne;
+      let n10 = self.vertical(node.nw, node.sw);
+      let n11 = self.centre(id);
+      let n12 = self.vertical(node.ne, node.se);
+      let n20 = node.sw;
+      let n21 = self.horizontal(node.sw, node.se);
+      let n22 = node.se;
+
+      // At full speed both halves of the jump advance 2^(k-1)
+      // generations, otherwise only the second half advances
+      let full_speed = k as usize == node.level as usize - 2;
+      let first_half = |hashlife: &mut HashLife, id| {
+        if full_speed {
+          hashlife.step(id, k - 1)
+        } else {
+          hashlife.centre(id)
+        }
+      };
+      let r00 = first_half(self, n00);
+      let r01 = first_half(self, n01);
+      let r02 = first_half(self, n02);
+      let r10 = first_half(self, n10);
+      let r11 = first_half(self, n11);
+      let r12 = first_half(self, n12);
+      let r20 = first_half(self, n20);
+      let r21 = first_half(self, n21);
+      let r22 = first_half(self, n22);
+
+      let second_k = if full_speed { k - 1 } else { k };
+      let nw = self.join(r00, r01, r10, r11);
+      let ne = self.join(r01, r02, r11, r12);
+      let sw = self.join(r10, r11, r20, r21);
+      let se = self.join(r11, r12, r21, r22);
+      let nw = self.step(nw, second_k);
+      let ne = self.step(ne, second_k);
+      let sw = self.step(sw, second_k);
+      let se = self.step(se, second_k);
+      self.join(nw, ne, sw, se)
+    };
+
+    self.results.insert((id, k), result);
+    result
+  }
+
+  // Plays one generation of a 4x4 node by counting neighbours directly
+  fn step_leaves(&mut self, id: NodeId) -> NodeId {
+    let mut grid = [[false; 4]; 4];
+    for (y, row) in grid.iter_mut().enumerate() {
+      for (x, alive) in row.iter_mut().enumerate() {
+        *alive = self.alive_in(id, x as i128, y as i128);
+      }
+    }
+
+    let mut next = [DEAD; 4];
+    for (i, (x, y)) in [(1, 1), (2, 1), (1, 2), (2, 2)].into_iter().enumerate() {
+      let mut alive_neighbours = 0;
+      for rel_y in 0..3 {
+        for rel_x in 0..3 {
+          if (rel_x, rel_y) != (1, 1) && grid[y + rel_y - 1][x + rel_x - 1] {
+            alive_neighbours += 1;
+          }
+        }
+      }
+
+      let alive = if grid[y][x] {
+        self.rule.survives(alive_neighbours)
+      } else {
+        self.rule.born(alive_neighbours)
+      };
+      next[i] = if alive { ALIVE } else { DEAD };
+    }
+    self.join(next[0], next[1], next[2], next[3])
+  }
+}
+
+impl Universe for HashLife {
+  fn width(&self) -> i64 {
+    self.width
+  }
+
+  fn height(&self) -> i64 {
+    self.height
+  }
+
+  fn tick(&self) -> u64 {
+    self.tick
+  }
+
+  fn set_tick(&mut self, tick: u64) {
+    self.tick = tick;
+  }
+
+  fn rule(&self) -> Rule {
+    self.rule
+  }
+
+  fn _tick(&mut self) {
+    self
+      .step_pow2(0)
+      .expect("one generation takes neither the tick nor a cell that far");
+  }
+
+  fn alive_at(&self, x: i64, y: i64) -> bool {
+    if !self.contains(x, y) {
+      return false;
+    }
+
+    let half = self.half();
+    self.alive_in(self.root, x as i128 + half, y as i128 + half)
+  }
+
+  fn set_alive(&mut self, x: i64, y: i64, alive: bool) -> Result<(), WorldError> {
+    HashLife::set_alive(self, x, y, alive);
+    Ok(())
+  }
+
+  fn in_bounds(&self, _x: i64, _y: i64) -> bool {
+    true
+  }
+
+  fn live_cells(&self) -> Vec<(i64, i64)> {
+    let mut cells = Vec::with_capacity(self.population() as usize);
+    let half = self.half();
+    self.collect_cells(self.root, -half, -half, &mut cells);
+    cells
+  }
+
+  fn viewport(&self) -> (i64, i64) {
+    HashLife::viewport(self)
+  }
+
+  fn render(&self) -> String {
+    let mut rendering = String::with_capacity(((self.width + 2) * (self.height + 1)) as usize);
+    let (left, top) = (self.viewport_x, self.viewport_y);
+    for y in top..=top + self.height {
+      for x in left..=left + self.width {
+        rendering.push(if self.alive_at(x, y) { 'o' } else { ' ' });
+      }
+      rendering.push('\n');
+    }
+    rendering
+  }
+}
+
+impl TryFrom<&World> for HashLife {
+  type Error = WorldError;
+
+  fn try_from(world: &World) -> Result<HashLife, WorldError> {
+    let rule = world.rule();
+    if rule.born(0) {
+      return Err(WorldError::UnsupportedRule { rule });
+    }
+
+    let mut hashlife = HashLife::empty(world.width(), world.height(), rule);
+    hashlife.tick = world.tick();
+    for y in 0..=world.height() {
+      for x in 0..=world.width() {
+        if world.cell_at(x, y).is_some_and(|cell| cell.alive) {
+          hashlife.set_alive(x, y, true);
+        }
+      }
+    }
+    Ok(hashlife)
+  }
+}
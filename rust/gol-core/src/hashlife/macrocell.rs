@@ -0,0 +1,242 @@
+use super::{HashLife, NodeId, ALIVE, DEAD, MAX_LEVEL};
+use crate::rule::Rule;
+use crate::world::{Settings, WorldError};
+use std::collections::HashMap;
+
+const HEADER: &str = "[M2]";
+
+// Leaves of the file are 8x8 squares, three levels above single cells
+const LEAF_LEVEL: u8 = 3;
+
+impl HashLife {
+  /// Reads a Golly macrocell (`.mc`) file straight into the quadtree,
+  /// with `(0, 0)` to `(width, height)` as the viewport.
+  ///
+  /// After the `[M2]` header, `#R` gives the rule and `#G` the
+  /// generation. Every other line is a node, numbered from 1 in the
+  /// order they appear, and the last one is the root, centred on the
+  /// origin. An 8x8 leaf is drawn with `.` for dead, `*` for alive and
+  /// `$` ending each row. A bigger node is written as its level and the
+  /// numbers of its four children, with 0 for an empty child. Levels
+  /// above 64 would put cells past the `i64` coordinates of the plane,
+  /// so they are rejected:
+  ///
+  /// ```text
+  /// [M2] (golly 4.2)
+  /// #R B3/S23
+  /// $$$$$.*$..*$***$
+  /// 4 0 0 0 1
+  /// ```
+  pub fn from_macrocell(width: i64, height: i64, text: &str) -> Result<HashLife, WorldError> {
+    let mut lines = text
+      .lines()
+      .enumerate()
+      .map(|(i, line)| (i + 1, line.trim()));
+    match lines.next() {
+      Some((_, line)) if line.starts_with(HEADER) => {}
+      Some((number, line)) => {
+        return Err(WorldError::UnknownFormat {
+          line: number,
+          header: line.to_string(),
+        })
+      }
+      None => {
+        return Err(WorldError::UnknownFormat {
+          line: 1,
+          header: String::new(),
+        })
+      }
+    }
+
+    let mut settings = Settings::default();
+    let mut tick = 0;
+    let mut body = Vec::new();
+    for (number, line) in lines {
+      if let Some(rule) = line.strip_prefix("#R") {
+        settings.rule = Rule::parse(rule)?;
+      } else if let Some(generation) = line.strip_prefix("#G") {
+        tick = generation.trim().parse().map_err(|_| {
+          invalid(
+            number,
+            &format!("{:?} is not a generation", generation.trim()),
+          )
+        })?;
+      } else if !line.is_empty() && !line.starts_with('#') {
+        body.push((number, line));
+      }
+    }
+    settings.check_unbounded()?;
+
+    let mut hashlife = HashLife::empty(width, height, settings.rule);
+    hashlife.tick = tick;
+
+    // Index 0 stands for an empty node of whatever level is needed
+    let mut ids: Vec<NodeId> = vec![DEAD];
+    for (number, line) in body {
+      let id = if line.starts_with(|char: char| char.is_ascii_digit()) {
+        hashlife.read_branch(number, line, &ids)?
+      } else {
+        hashlife.read_leaf(number, line)?
+      };
+      ids.push(id);
+    }
+
+    if ids.len() > 1 {
+      hashlife.root = ids[ids.len() - 1];
+      while hashlife.level() < LEAF_LEVEL as usize {
+        hashlife.expand();
+      }
+    }
+    Ok(hashlife)
+  }
+
+  /// Writes the universe as a Golly macrocell (`.mc`) file, sharing
+  /// every repeated node the way the quadtree does.
+  pub fn to_macrocell(&self) -> String {
+    let mut macrocell = format!("{} (gol-core)\n#R {}\n", HEADER, self.rule);
+    if self.tick > 0 {
+      macrocell += &format!("#G {}\n", self.tick);
+    }
+
+    let mut numbers = HashMap::new();
+    self.write_node(self.root, &mut numbers, &mut macrocell);
+    macrocell
+  }
+
+  // Draws an 8x8 leaf line into a node, row by row
+  fn read_leaf(&mut self, number: usize, line: &str) -> Result<NodeId, WorldError> {
+    let mut grid = [[false; 8]; 8];
+    let (mut x, mut y) = (0, 0);
+    for char in line.chars() {
+      match char {
+        '$' => {
+          x = 0;
+          y += 1;
+        }
+        '.' | '*' if x < 8 && y < 8 => {
+          grid[y][x] = char == '*';
+          x += 1;
+        }
+        '.' | '*' => return Err(invalid(number, "a leaf holds at most 8x8 cells")),
+        char => return Err(invalid(number, &format!("unexpected character {:?}", char))),
+      }
+    }
+    Ok(self.node_from_grid(&grid, 0, 0, LEAF_LEVEL))
+  }
+
+  fn node_from_grid(&mut self, grid: &[[bool; 8]; 8], x: usize, y: usize, level: u8) -> NodeId {
+    if level == 0 {
+      return if grid[y][x] { ALIVE } else { DEAD };
+    }
+
+    let half = 1 << (level - 1);
+    let nw = self.node_from_grid(grid, x, y, level - 1);
+    let ne = self.node_from_grid(grid, x + half, y, level - 1);
+    let sw = self.node_from_grid(grid, x, y + half, level - 1);
+    let se = self.node_from_grid(grid, x + half, y + half, level - 1);
+    self.join(nw, ne, sw, se)
+  }
+
+  // Reads `level nw ne sw se`. Level 1 nodes list the states of their
+  // four cells, bigger nodes the numbers of earlier nodes
+  fn read_branch(
+    &mut self,
+    number: usize,
+    line: &str,
+    ids: &[NodeId],
+  ) -> Result<NodeId, WorldError> {
+    let fields: Vec<u64> = line
+      .split_whitespace()
+      .map(|field| field.parse())
+      .collect::<Result<_, _>>()
+      .map_err(|_| invalid(number, &format!("{:?} is not a node", line)))?;
+    let [level, nw, ne, sw, se] = fields[..] else {
+      return Err(invalid(number, "a node needs a level and four children"));
+    };
+    if level == 0 || level > MAX_LEVEL as u64 {
+      return Err(invalid(
+        number,
+        &format!("levels run from 1 to {}, not {}", MAX_LEVEL, level),
+      ));
+    }
+    let level = level as u8;
+
+    let mut quadrants = [DEAD; 4];
+    for (quadrant, child) in quadrants.iter_mut().zip([nw, ne, sw, se]) {
+      *quadrant = if level == 1 {
+        match child {
+          0 => DEAD,
+          1 => ALIVE,
+          state => return Err(invalid(number, &format!("{} is not a cell state", state))),
+        }
+      } else if child == 0 {
+        self.empty_node(level as usize - 1)
+      } else {
+        let id = *ids
+          .get(child as usize)
+          .ok_or_else(|| invalid(number, &format!("node {} isn't defined yet", child)))?;
+        if self.nodes[id as usize].level != level - 1 {
+          return Err(invalid(
+            number,
+            &format!("node {} is not on level {}", child, level - 1),
+          ));
+        }
+        id
+      };
+    }
+    Ok(self.join(quadrants[0], quadrants[1], quadrants[2], quadrants[3]))
+  }
+
+  // Children are written before their parents, and empty nodes are
+  // never written at all, they are the 0 in their parent's line
+  fn write_node(
+    &self,
+    id: NodeId,
+    numbers: &mut HashMap<NodeId, usize>,
+    macrocell: &mut String,
+  ) -> usize {
+    let node = self.nodes[id as usize];
+    if node.population == 0 {
+      return 0;
+    }
+    if let Some(&number) = numbers.get(&id) {
+      return number;
+    }
+
+    if node.level == LEAF_LEVEL {
+      let mut rows = Vec::new();
+      for y in 0..8 {
+        let row: String = (0..8)
+          .map(|x| if self.alive_in(id, x, y) { '*' } else { '.' })
+          .collect();
+        rows.push(row.trim_end_matches('.').to_string());
+      }
+      while rows.last().is_some_and(|row| row.is_empty()) {
+        rows.pop();
+      }
+      for row in rows {
+        *macrocell += &row;
+        macrocell.push('$');
+      }
+    } else {
+      let children = [node.nw, node.ne, node.sw, node.se]
+        .map(|child| self.write_node(child, numbers, macrocell));
+      *macrocell += &format!(
+        "{} {} {} {} {}",
+        node.level, children[0], children[1], children[2], children[3]
+      );
+    }
+    macrocell.push('\n');
+
+    let number = numbers.len() + 1;
+    numbers.insert(id, number);
+    number
+  }
+}
+
+fn invalid(line: usize, reason: &str) -> WorldError {
+  WorldError::InvalidPattern {
+    line,
+    reason: reason.to_string(),
+  }
+}
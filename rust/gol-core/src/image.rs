@@ -0,0 +1,180 @@
+use crate::universe::Universe;
+use crate::world::WorldError;
+use std::ops::Range;
+
+mod deflate;
+mod gif;
+mod png;
+
+// Palette indices of the pixels in a frame
+const DEAD: u8 = 0;
+const ALIVE: u8 = 1;
+const GRID: u8 = 2;
+
+/// How universes are drawn when they are exported as PNG, APNG or
+/// animated GIF images.
+///
+/// Every cell is a square of `cell_size` pixels. With gridlines, a one
+/// pixel line runs around every cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageOptions {
+  /// Width and height of each cell in pixels, `4` by default.
+  pub cell_size: u32,
+  /// Colour of living cells as red, green and blue, black by default.
+  pub alive: [u8; 3],
+  /// Colour of dead cells as red, green and blue, white by default.
+  pub dead: [u8; 3],
+  /// Colour of the gridlines, or `None` for no gridlines (the default).
+  pub grid: Option<[u8; 3]>,
+  /// How long each frame of an animation is shown, in milliseconds,
+  /// `100` by default.
+  pub frame_delay: u16,
+}
+
+impl Default for ImageOptions {
+  fn default() -> ImageOptions {
+    ImageOptions {
+      cell_size: 4,
+      alive: [0, 0, 0],
+      dead: [255, 255, 255],
+      grid: None,
+      frame_delay: 100,
+    }
+  }
+}
+
+/// One generation drawn as pixels, ready to be encoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+  width: u32,
+  height: u32,
+  pixels: Vec<u8>,
+}
+
+impl Frame {
+  /// Returns the width of the frame in pixels.
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  /// Returns the height of the frame in pixels.
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+
+  // Stands in for an animation without frames, as the formats need at
+  // least one
+  fn blank() -> Frame {
+    Frame {
+      width: 1,
+      height: 1,
+      pixels: vec![DEAD],
+    }
+  }
+}
+
+impl ImageOptions {
+  // GIF stores sizes in 16 bits, and that is plenty for PNG too
+  const MAX_SIZE: u64 = u16::MAX as u64;
+
+  /// Draws the region `render` covers, from the universe's viewport to
+  /// `width` columns and `height` rows past it.
+  pub fn frame(&self, universe: &dyn Universe) -> Result<Frame, WorldError> {
+    let cell_size = self.cell_size.max(1) as u64;
+    let (columns, rows) = (universe.width() as u64 + 1, universe.height() as u64 + 1);
+    let (pitch, border) = match self.grid {
+      Some(_) => (cell_size + 1, 1),
+      None => (cell_size, 0),
+    };
+
+    let width = columns.saturating_mul(pitch).saturating_add(border);
+    let height = rows.saturating_mul(pitch).saturating_add(border);
+    if width > ImageOptions::MAX_SIZE || height > ImageOptions::MAX_SIZE {
+      return Err(WorldError::ImageTooLarge { width, height });
+    }
+
+    // Each row of cells is drawn once as a line of pixels, then repeated
+    // for every pixel row of the cells
+    let (viewport_x, viewport_y) = universe.viewport();
+    let grid_line = vec![GRID; width as usize];
+    let mut pixels = Vec::with_capacity((width * height) as usize);
+    for y in 0..rows as i64 {
+      if border > 0 {
+        pixels.extend(&grid_line);
+      }
+
+      let mut line = Vec::with_capacity(width as usize);
+      for x in 0..columns as i64 {
+        if border > 0 {
+          line.push(GRID);
+        }
+        let alive = universe.alive_at(viewport_x + x, viewport_y + y);
+        let colour = if alive { ALIVE } else { DEAD };
+        line.extend(std::iter::repeat_n(colour, cell_size as usize));
+      }
+      if border > 0 {
+        line.push(GRID);
+      }
+
+      for _ in 0..cell_size {
+        pixels.extend(&line);
+      }
+    }
+    if border > 0 {
+      pixels.extend(&grid_line);
+    }
+
+    Ok(Frame {
+      width: width as u32,
+      height: height as u32,
+      pixels,
+    })
+  }
+
+  /// Plays the universe up to the start of a range of generations, then
+  /// draws one frame per generation in it. Generations the universe has
+  /// already played are skipped.
+  pub fn record(
+    &self,
+    universe: &mut dyn Universe,
+    generations: Range<u64>,
+  ) -> Result<Vec<Frame>, WorldError> {
+    while universe.tick() < generations.start {
+      universe._tick();
+    }
+
+    let mut frames = Vec::new();
+    while universe.tick() < generations.end {
+      frames.push(self.frame(universe)?);
+      universe._tick();
+    }
+    Ok(frames)
+  }
+
+  /// Encodes a frame as a PNG image.
+  pub fn png(&self, frame: &Frame) -> Vec<u8> {
+    png::encode(
+      &self.palette(),
+      std::slice::from_ref(frame),
+      self.frame_delay,
+    )
+  }
+
+  /// Encodes frames as an animated PNG (APNG) that loops forever. Viewers
+  /// without APNG support show the first frame, and no frames at all
+  /// give a single blank pixel.
+  pub fn apng(&self, frames: &[Frame]) -> Vec<u8> {
+    png::encode(&self.palette(), frames, self.frame_delay)
+  }
+
+  /// Encodes frames as an animated GIF that loops forever, where no
+  /// frames at all give a single blank pixel.
+  pub fn gif(&self, frames: &[Frame]) -> Vec<u8> {
+    gif::encode(&self.palette(), frames, self.frame_delay)
+  }
+
+  // Colours in the order of the palette indices
+  fn palette(&self) -> [[u8; 3]; 3] {
+    [self.dead, self.alive, self.grid.unwrap_or(self.dead)]
+  }
+}
@@ -0,0 +1,226 @@
+// A zlib stream (RFC 1950) around a deflate stream (RFC 1951) that
+// uses the fixed Huffman codes, so no code tables have to be built and
+// sent. Frames are mostly long runs of the same pixel, which LZ77
+// matches shrink well even without tailored codes. Data that wouldn't
+// shrink is kept in stored blocks instead
+
+const WINDOW: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+// How many earlier positions with the same hash are tried per match
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: u32 = 15;
+
+#[rustfmt::skip]
+const LENGTH_BASES: [u16; 29] = [
+  3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+  35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+#[rustfmt::skip]
+const LENGTH_EXTRA_BITS: [u8; 29] = [
+  0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+  3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+#[rustfmt::skip]
+const DISTANCE_BASES: [u16; 30] = [
+  1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+  257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+#[rustfmt::skip]
+const DISTANCE_EXTRA_BITS: [u8; 30] = [
+  0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6,
+  7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+
+pub(super) fn zlib(data: &[u8]) -> Vec<u8> {
+  // A 32K window and no preset dictionary
+  let mut stream = vec![0x78, 0x01];
+
+  let compressed = fixed_block(data);
+  if compressed.len() < data.len() + data.len() / 0xFFFF * 5 + 5 {
+    stream.extend(compressed);
+  } else {
+    stream.extend(stored_blocks(data));
+  }
+
+  stream.extend(adler32(data).to_be_bytes());
+  stream
+}
+
+pub(super) fn adler32(data: &[u8]) -> u32 {
+  const MODULUS: u32 = 65521;
+  let (mut a, mut b) = (1u32, 0u32);
+  // 5552 bytes is the most that can be summed before b could overflow,
+  // so the modulus only needs taking once per chunk
+  for chunk in data.chunks(5552) {
+    for &byte in chunk {
+      a += byte as u32;
+      b += a;
+    }
+    a %= MODULUS;
+    b %= MODULUS;
+  }
+  b << 16 | a
+}
+
+fn stored_blocks(data: &[u8]) -> Vec<u8> {
+  let mut blocks = Vec::with_capacity(data.len() + 5);
+  let chunks: Vec<&[u8]> = if data.is_empty() {
+    vec![&[]]
+  } else {
+    data.chunks(0xFFFF).collect()
+  };
+
+  for (i, chunk) in chunks.iter().enumerate() {
+    let last = i + 1 == chunks.len();
+    let length = chunk.len() as u16;
+    blocks.push(last as u8);
+    blocks.extend(length.to_le_bytes());
+    blocks.extend((!length).to_le_bytes());
+    blocks.extend(*chunk);
+  }
+  blocks
+}
+
+fn fixed_block(data: &[u8]) -> Vec<u8> {
+  let mut bits = BitWriter::default();
+  // The final block, compressed with the fixed codes
+  bits.write(1, 1);
+  bits.write(1, 2);
+
+  let mut matcher = Matcher::new(data);
+  let mut i = 0;
+  while i < data.len() {
+    let (length, distance) = matcher.longest_match(i);
+    if length >= MIN_MATCH {
+      write_length(&mut bits, length);
+      write_distance(&mut bits, distance);
+      for j in i..i + length {
+        matcher.insert(j);
+      }
+      i += length;
+    } else {
+      write_symbol(&mut bits, data[i] as u16);
+      matcher.insert(i);
+      i += 1;
+    }
+  }
+
+  write_symbol(&mut bits, 256);
+  bits.finish()
+}
+
+// Remembers where every three byte sequence was seen, as chains of
+// earlier positions that share a hash
+struct Matcher<'a> {
+  data: &'a [u8],
+  head: Vec<usize>,
+  previous: Vec<usize>,
+}
+
+impl<'a> Matcher<'a> {
+  fn new(data: &'a [u8]) -> Matcher<'a> {
+    Matcher {
+      data,
+      head: vec![usize::MAX; 1 << HASH_BITS],
+      previous: vec![usize::MAX; data.len()],
+    }
+  }
+
+  fn hash(&self, i: usize) -> usize {
+    let data = self.data;
+    let key = (data[i] as u32) << 16 | (data[i + 1] as u32) << 8 | data[i + 2] as u32;
+    (key.wrapping_mul(0x9E37_79B1) >> (32 - HASH_BITS)) as usize
+  }
+
+  fn insert(&mut self, i: usize) {
+    if i + MIN_MATCH <= self.data.len() {
+      let key = self.hash(i);
+      self.previous[i] = self.head[key];
+      self.head[key] = i;
+    }
+  }
+
+  // Follows the chain for the longest run of bytes that repeats here
+  fn longest_match(&self, i: usize) -> (usize, usize) {
+    let data = self.data;
+    if i + MIN_MATCH > data.len() {
+      return (0, 0);
+    }
+
+    let max_length = MAX_MATCH.min(data.len() - i);
+    let (mut best_length, mut best_distance) = (0, 0);
+    let mut candidate = self.head[self.hash(i)];
+    let mut chain = 0;
+    while candidate != usize::MAX && i - candidate <= WINDOW && chain < MAX_CHAIN {
+      let length = (0..max_length)
+        .take_while(|&j| data[candidate + j] == data[i + j])
+        .count();
+      if length > best_length {
+        (best_length, best_distance) = (length, i - candidate);
+        if length == max_length {
+          break;
+        }
+      }
+      candidate = self.previous[candidate];
+      chain += 1;
+    }
+    (best_length, best_distance)
+  }
+}
+
+fn write_symbol(bits: &mut BitWriter, symbol: u16) {
+  match symbol {
+    0..=143 => bits.write_code(0x30 + symbol, 8),
+    144..=255 => bits.write_code(0x190 + symbol - 144, 9),
+    256..=279 => bits.write_code(symbol - 256, 7),
+    _ => bits.write_code(0xC0 + symbol - 280, 8),
+  }
+}
+
+fn write_length(bits: &mut BitWriter, length: usize) {
+  let code = LENGTH_BASES.partition_point(|&base| base as usize <= length) - 1;
+  write_symbol(bits, 257 + code as u16);
+  let extra = length as u16 - LENGTH_BASES[code];
+  bits.write(extra as u32, LENGTH_EXTRA_BITS[code]);
+}
+
+fn write_distance(bits: &mut BitWriter, distance: usize) {
+  let code = DISTANCE_BASES.partition_point(|&base| base as usize <= distance) - 1;
+  bits.write_code(code as u16, 5);
+  let extra = distance as u16 - DISTANCE_BASES[code];
+  bits.write(extra as u32, DISTANCE_EXTRA_BITS[code]);
+}
+
+// Deflate packs values from the least significant bit up, but Huffman
+// codes are sent from their most significant bit
+#[derive(Default)]
+struct BitWriter {
+  bytes: Vec<u8>,
+  buffer: u64,
+  count: u8,
+}
+
+impl BitWriter {
+  fn write(&mut self, value: u32, count: u8) {
+    self.buffer |= (value as u64) << self.count;
+    self.count += count;
+    while self.count >= 8 {
+      self.bytes.push(self.buffer as u8);
+      self.buffer >>= 8;
+      self.count -= 8;
+    }
+  }
+
+  fn write_code(&mut self, code: u16, length: u8) {
+    let reversed = code.reverse_bits() >> (16 - length);
+    self.write(reversed as u32, length);
+  }
+
+  fn finish(mut self) -> Vec<u8> {
+    if self.count > 0 {
+      self.bytes.push(self.buffer as u8);
+    }
+    self.bytes
+  }
+}
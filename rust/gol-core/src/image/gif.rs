@@ -0,0 +1,134 @@
+use super::Frame;
+use std::collections::HashMap;
+
+// Three colours fit the smallest colour table worth having, four
+// entries, which pixel codes of 2 bits can index
+const MIN_CODE_SIZE: u8 = 2;
+const MAX_CODE_SIZE: u8 = 12;
+const MAX_CODES: u16 = 1 << MAX_CODE_SIZE;
+
+pub(super) fn encode(palette: &[[u8; 3]], frames: &[Frame], frame_delay: u16) -> Vec<u8> {
+  let blank = [Frame::blank()];
+  let frames = if frames.is_empty() { &blank } else { frames };
+  let (width, height) = (frames[0].width as u16, frames[0].height as u16);
+
+  let mut gif = b"GIF89a".to_vec();
+  gif.extend(width.to_le_bytes());
+  gif.extend(height.to_le_bytes());
+  // A global colour table of 2^(1 + 1) entries, 2 bits per primary
+  // colour, then the background colour and no aspect ratio
+  gif.extend([0x91, 0, 0]);
+  for i in 0..1 << MIN_CODE_SIZE {
+    gif.extend(palette.get(i).unwrap_or(&[0, 0, 0]));
+  }
+
+  // The Netscape extension asks viewers to loop forever
+  gif.extend([0x21, 0xFF, 0x0B]);
+  gif.extend(b"NETSCAPE2.0");
+  gif.extend([0x03, 0x01, 0x00, 0x00, 0x00]);
+
+  // Delays are in hundredths of a second
+  let delay = frame_delay / 10;
+  for frame in frames {
+    // Graphic control: leave the frame in place, no transparency
+    gif.extend([0x21, 0xF9, 0x04, 0x04]);
+    gif.extend(delay.to_le_bytes());
+    gif.extend([0x00, 0x00]);
+
+    // Image descriptor: the whole screen, no local colour table
+    gif.push(0x2C);
+    gif.extend(0u16.to_le_bytes());
+    gif.extend(0u16.to_le_bytes());
+    gif.extend(width.to_le_bytes());
+    gif.extend(height.to_le_bytes());
+    gif.push(0x00);
+
+    gif.push(MIN_CODE_SIZE);
+    for block in lzw(&frame.pixels).chunks(255) {
+      gif.push(block.len() as u8);
+      gif.extend(block);
+    }
+    gif.push(0x00);
+  }
+
+  gif.push(0x3B);
+  gif
+}
+
+// Variable width LZW as GIF uses it. Codes start one bit wider than the
+// pixels and grow to 12 bits, after which the table is cleared
+fn lzw(pixels: &[u8]) -> Vec<u8> {
+  let clear: u16 = 1 << MIN_CODE_SIZE;
+  let end = clear + 1;
+
+  let mut bits = BitWriter::default();
+  let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+  let mut code_size = MIN_CODE_SIZE + 1;
+  let mut next_code = end + 1;
+  bits.write(clear, code_size);
+
+  let Some((&first, rest)) = pixels.split_first() else {
+    bits.write(end, code_size);
+    return bits.finish();
+  };
+
+  let mut prefix = first as u16;
+  for &pixel in rest {
+    if let Some(&code) = table.get(&(prefix, pixel)) {
+      prefix = code;
+      continue;
+    }
+
+    write_code(&mut bits, prefix, &mut code_size, next_code);
+    if next_code < MAX_CODES {
+      table.insert((prefix, pixel), next_code);
+      next_code += 1;
+    } else {
+      bits.write(clear, code_size);
+      table.clear();
+      code_size = MIN_CODE_SIZE + 1;
+      next_code = end + 1;
+    }
+    prefix = pixel as u16;
+  }
+
+  write_code(&mut bits, prefix, &mut code_size, next_code);
+  write_code(&mut bits, end, &mut code_size, next_code);
+  bits.finish()
+}
+
+// The decoder adds its table entries one code behind the encoder, so
+// codes widen once the next free code no longer fits, not before
+fn write_code(bits: &mut BitWriter, code: u16, code_size: &mut u8, next_code: u16) {
+  bits.write(code, *code_size);
+  if next_code >= 1 << *code_size && *code_size < MAX_CODE_SIZE {
+    *code_size += 1;
+  }
+}
+
+// GIF packs codes from the least significant bit up
+#[derive(Default)]
+struct BitWriter {
+  bytes: Vec<u8>,
+  buffer: u32,
+  count: u8,
+}
+
+impl BitWriter {
+  fn write(&mut self, code: u16, size: u8) {
+    self.buffer |= (code as u32) << self.count;
+    self.count += size;
+    while self.count >= 8 {
+      self.bytes.push(self.buffer as u8);
+      self.buffer >>= 8;
+      self.count -= 8;
+    }
+  }
+
+  fn finish(mut self) -> Vec<u8> {
+    if self.count > 0 {
+      self.bytes.push(self.buffer as u8);
+    }
+    self.bytes
+  }
+}
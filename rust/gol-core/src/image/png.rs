@@ -0,0 +1,115 @@
+use super::deflate;
+use super::Frame;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+// Writes a plain PNG for a single frame, and an APNG otherwise. Pixels
+// are palette indices, so every image is colour type 3 with 8 bits per
+// pixel and a PLTE chunk
+pub(super) fn encode(palette: &[[u8; 3]], frames: &[Frame], frame_delay: u16) -> Vec<u8> {
+  let blank = [Frame::blank()];
+  let frames = if frames.is_empty() { &blank } else { frames };
+  let first = &frames[0];
+
+  let mut png = SIGNATURE.to_vec();
+  let mut header = Vec::with_capacity(13);
+  header.extend(first.width.to_be_bytes());
+  header.extend(first.height.to_be_bytes());
+  // Bit depth, colour type, compression, filter and interlace method
+  header.extend([8, 3, 0, 0, 0]);
+  chunk(&mut png, b"IHDR", &header);
+
+  let animated = frames.len() > 1;
+  if animated {
+    let mut control = Vec::with_capacity(8);
+    control.extend((frames.len() as u32).to_be_bytes());
+    // Loop forever
+    control.extend(0u32.to_be_bytes());
+    chunk(&mut png, b"acTL", &control);
+  }
+
+  chunk(&mut png, b"PLTE", palette.as_flattened());
+
+  // fcTL and fdAT chunks share one sequence of numbers
+  let mut sequence = 0u32;
+  for (i, frame) in frames.iter().enumerate() {
+    if animated {
+      let mut control = Vec::with_capacity(26);
+      control.extend(sequence.to_be_bytes());
+      control.extend(frame.width.to_be_bytes());
+      control.extend(frame.height.to_be_bytes());
+      // No offset, then the delay as a fraction of a second
+      control.extend(0u32.to_be_bytes());
+      control.extend(0u32.to_be_bytes());
+      control.extend(frame_delay.to_be_bytes());
+      control.extend(1000u16.to_be_bytes());
+      // Leave the frame in place and draw over the last one
+      control.extend([0, 0]);
+      chunk(&mut png, b"fcTL", &control);
+      sequence += 1;
+    }
+
+    let data = deflate::zlib(&scanlines(frame));
+    if i == 0 {
+      chunk(&mut png, b"IDAT", &data);
+    } else {
+      let mut frame_data = Vec::with_capacity(data.len() + 4);
+      frame_data.extend(sequence.to_be_bytes());
+      frame_data.extend(data);
+      chunk(&mut png, b"fdAT", &frame_data);
+      sequence += 1;
+    }
+  }
+
+  chunk(&mut png, b"IEND", &[]);
+  png
+}
+
+// Every row starts with its filter type, and 0 leaves it unfiltered
+fn scanlines(frame: &Frame) -> Vec<u8> {
+  let width = frame.width as usize;
+  let mut scanlines = Vec::with_capacity((width + 1) * frame.height as usize);
+  for row in frame.pixels.chunks(width) {
+    scanlines.push(0);
+    scanlines.extend(row);
+  }
+  scanlines
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+  png.extend((data.len() as u32).to_be_bytes());
+  png.extend(kind);
+  png.extend(data);
+  png.extend(crc32(kind.iter().chain(data)).to_be_bytes());
+}
+
+fn crc32<'a, I: IntoIterator<Item = &'a u8>>(bytes: I) -> u32 {
+  let crc = bytes.into_iter().fold(!0u32, |crc, &byte| {
+    CRC_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ crc >> 8
+  });
+  !crc
+}
+
+// The table for the reflected polynomial 0xEDB88320, worked out while
+// compiling
+const fn crc_table() -> [u32; 256] {
+  let mut table = [0; 256];
+  let mut i = 0;
+  while i < 256 {
+    let mut crc = i as u32;
+    let mut bit = 0;
+    while bit < 8 {
+      crc = if crc & 1 != 0 {
+        0xEDB8_8320 ^ crc >> 1
+      } else {
+        crc >> 1
+      };
+      bit += 1;
+    }
+    table[i] = crc;
+    i += 1;
+  }
+  table
+}
@@ -0,0 +1,75 @@
+//! The Game of Life engine behind the `play` binary.
+//!
+//! Everything here is plain computation, there is no terminal I/O, so
+//! other binaries and tests can link the engine and drive it themselves.
+//! [`World`] is the reference implementation shared with the other
+//! languages. [`BitWorld`] and [`HashLife`] are faster backends behind
+//! the same [`Universe`] trait, and [`SparseWorld`] grows without bounds
+//! as patterns expand.
+//!
+//! [`Pattern`] reads and writes the common pattern file formats, RLE,
+//! plaintext and Life 1.05/1.06, and places them in any of the worlds.
+//! Golly macrocells load straight into a [`HashLife`] quadtree instead,
+//! and frames printed by `render` can be read back at their generation.
+//!
+//! [`ImageOptions`] draws generations as PNG images, or plays them as
+//! animated PNGs and GIFs.
+//! A [`Renderer`] draws a universe as text, such as the
+//! [`TextRenderer`] behind `render` or the [`SvgRenderer`] for figures.
+//! [`HalfBlockRenderer`] and [`BrailleRenderer`] pack several cells into
+//! each character, so larger worlds fit in a terminal.
+//!
+//! A [`CycleDetector`] notices when a world has settled into still
+//! lifes and oscillators, and reports the [`Cycle`] it settled into.
+//! A [`Census`] then splits the world into islands and names each of
+//! them by its [`apgcode`], with the [`Velocity`] of any spaceships.
+//! While a [`World`] plays it can keep a [`Timeline`] of population,
+//! births, deaths and activity for every generation, to export as CSV
+//! or JSON.
+//! A [`Search`] plays thousands of seeded soups to the end, the way
+//! apgsearch does, and keeps [`SearchResults`] of the objects they left.
+//! [`Lifespan`] measures how long a single pattern, such as a
+//! methuselah, takes to stabilise, and what it leaves behind.
+
+#![warn(missing_docs)]
+
+mod bit_world;
+mod census;
+mod cycle;
+mod frame;
+mod hashlife;
+mod image;
+mod life;
+mod lifespan;
+mod pattern;
+mod plaintext;
+mod random;
+mod render;
+mod rle;
+mod rule;
+mod search;
+mod sparse_world;
+mod svg;
+mod timeline;
+mod topology;
+mod universe;
+mod workers;
+mod world;
+
+pub use bit_world::BitWorld;
+pub use census::{apgcode, islands, object_name, Census, Connectivity, Island, Velocity};
+pub use cycle::{Cycle, CycleDetector};
+pub use hashlife::HashLife;
+pub use image::{Frame, ImageOptions};
+pub use lifespan::Lifespan;
+pub use pattern::Pattern;
+pub use random::Random;
+pub use render::{BrailleRenderer, HalfBlockRenderer, Renderer, TextRenderer};
+pub use rule::Rule;
+pub use search::{Search, SearchResults};
+pub use sparse_world::SparseWorld;
+pub use svg::SvgRenderer;
+pub use timeline::{GenerationStats, Timeline};
+pub use topology::Topology;
+pub use universe::{BoundingBox, Universe};
+pub use world::{Cell, Settings, World, WorldError};
@@ -0,0 +1,222 @@
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+use crate::world::WorldError;
+
+const LIFE_105: &str = "#Life 1.05";
+const LIFE_106: &str = "#Life 1.06";
+
+impl Pattern {
+  /// Parses a Life 1.05 or Life 1.06 (`.lif`) pattern, whichever its
+  /// `#Life` header names.
+  pub fn from_life(text: &str) -> Result<Pattern, WorldError> {
+    let (number, header) = header(text);
+    match header {
+      LIFE_105 => Pattern::from_life105(text),
+      LIFE_106 => Pattern::from_life106(text),
+      header => Err(WorldError::UnknownFormat {
+        line: number,
+        header: header.to_string(),
+      }),
+    }
+  }
+
+  /// Parses a Life 1.05 pattern.
+  ///
+  /// `#D` lines are comments, `#N` asks for Conway's rule and `#R`
+  /// gives another as survival/birth. Each `#P x y` line starts a block
+  /// of rows with its top left corner at `(x, y)`, `*` for alive and
+  /// `.` for dead. The pattern is cropped to its living cells:
+  ///
+  /// ```text
+  /// #Life 1.05
+  /// #D Glider
+  /// #N
+  /// #P -1 -1
+  /// .*
+  /// ..*
+  /// ***
+  /// ```
+  pub fn from_life105(text: &str) -> Result<Pattern, WorldError> {
+    check_header(text, LIFE_105)?;
+
+    let mut comments = Vec::new();
+    let mut rule = None;
+    let mut cells = Vec::new();
+    let (mut block_x, mut block_y) = (0, 0);
+    for (i, line) in text.lines().enumerate() {
+      let line = line.trim();
+      let number = i + 1;
+      if line.is_empty() {
+        continue;
+      }
+
+      if let Some(directive) = line.strip_prefix('#') {
+        let (kind, value) = directive.split_at(directive.len().min(1));
+        match kind {
+          "D" => comments.push(value.trim().to_string()),
+          "N" => rule = Some(Rule::CONWAY),
+          "R" => rule = Some(Rule::parse(value)?),
+          "P" => (block_x, block_y) = coordinates(number, value)?,
+          // The #Life header, and lines other tools add
+          _ => {}
+        }
+        continue;
+      }
+
+      for (x, char) in line.chars().enumerate() {
+        match char {
+          '*' => {
+            let x = block_x
+              .checked_add(x as i64)
+              .ok_or_else(|| too_large(number))?;
+            cells.push((x, block_y));
+          }
+          '.' => {}
+          char => {
+            return Err(WorldError::InvalidPattern {
+              line: number,
+              reason: format!("unexpected character {:?}", char),
+            })
+          }
+        }
+      }
+      block_y = block_y.checked_add(1).ok_or_else(|| too_large(number))?;
+    }
+
+    let mut pattern = cropped(text, cells)?;
+    pattern.comments = comments;
+    pattern.rule = rule;
+    Ok(pattern)
+  }
+
+  /// Parses a Life 1.06 pattern, which lists the `x y` location of one
+  /// living cell per line after its header. The pattern is cropped to
+  /// its living cells.
+  pub fn from_life106(text: &str) -> Result<Pattern, WorldError> {
+    check_header(text, LIFE_106)?;
+
+    let mut cells = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+      let line = line.trim();
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+      cells.push(coordinates(i + 1, line)?);
+    }
+    cropped(text, cells)
+  }
+
+  /// Writes the pattern as Life 1.05, in a single block at `(0, 0)`.
+  /// The format only has descriptions, so the name and author are
+  /// written as the first `#D` lines and read back as comments.
+  pub fn to_life105(&self) -> String {
+    let mut life = format!("{}\n", LIFE_105);
+    let descriptions = self.name.iter().chain(&self.author).chain(&self.comments);
+    for description in descriptions {
+      life += &format!("#D {}\n", description);
+    }
+
+    match self.rule.unwrap_or_default() {
+      Rule::CONWAY => life += "#N\n",
+      rule => life += &format!("#R {}\n", survival_birth(rule)),
+    }
+
+    life += "#P 0 0\n";
+    for row in self.grid() {
+      let length = row.iter().rposition(|&alive| alive).map_or(0, |x| x + 1);
+      // An empty line could be mistaken for the end of the block
+      if length == 0 {
+        life += ".";
+      }
+      life.extend(
+        row[..length]
+          .iter()
+          .map(|&alive| if alive { '*' } else { '.' }),
+      );
+      life.push('\n');
+    }
+    life
+  }
+
+  /// Writes the pattern as Life 1.06, one living cell per line. The
+  /// format has no room for the name, comments or rule.
+  pub fn to_life106(&self) -> String {
+    let mut life = format!("{}\n", LIFE_106);
+    for &(x, y) in &self.cells {
+      life += &format!("{} {}\n", x, y);
+    }
+    life
+  }
+}
+
+// The first line that isn't blank, with its line number
+fn header(text: &str) -> (usize, &str) {
+  text
+    .lines()
+    .enumerate()
+    .map(|(i, line)| (i + 1, line.trim()))
+    .find(|(_, line)| !line.is_empty())
+    .unwrap_or((1, ""))
+}
+
+fn check_header(text: &str, expected: &str) -> Result<(), WorldError> {
+  let (line, header) = header(text);
+  if header != expected {
+    return Err(WorldError::UnknownFormat {
+      line,
+      header: header.to_string(),
+    });
+  }
+  Ok(())
+}
+
+fn coordinates(line: usize, value: &str) -> Result<(i64, i64), WorldError> {
+  let numbers: Vec<&str> = value.split_whitespace().collect();
+  match numbers[..] {
+    [x, y] => match (x.parse(), y.parse()) {
+      (Ok(x), Ok(y)) => Ok((x, y)),
+      _ => Err(WorldError::InvalidPattern {
+        line,
+        reason: format!("{:?} are not coordinates", value.trim()),
+      }),
+    },
+    _ => Err(WorldError::InvalidPattern {
+      line,
+      reason: format!("expected x y, not {:?}", value.trim()),
+    }),
+  }
+}
+
+// Both formats place cells anywhere on the plane, but a pattern has to
+// be narrow enough for its columns and rows to be counted
+fn cropped(text: &str, cells: Vec<(i64, i64)>) -> Result<Pattern, WorldError> {
+  let (mut min, mut max) = ((i64::MAX, i64::MAX), (i64::MIN, i64::MIN));
+  for &(x, y) in &cells {
+    min = (min.0.min(x), min.1.min(y));
+    max = (max.0.max(x), max.1.max(y));
+  }
+
+  let too_wide = |min: i64, max: i64| max.abs_diff(min) >= i64::MAX as u64;
+  if !cells.is_empty() && (too_wide(min.0, max.0) || too_wide(min.1, max.1)) {
+    return Err(too_large(text.lines().count().max(1)));
+  }
+  Ok(Pattern::new(cells))
+}
+
+fn too_large(line: usize) -> WorldError {
+  WorldError::InvalidPattern {
+    line,
+    reason: "the pattern spans too many columns or rows".to_string(),
+  }
+}
+
+// The older notation lists survival first, without letters
+fn survival_birth(rule: Rule) -> String {
+  let counts = |matches: fn(&Rule, usize) -> bool| {
+    (0..=8)
+      .filter(|&count| matches(&rule, count))
+      .map(|count| count.to_string())
+      .collect::<String>()
+  };
+  format!("{}/{}", counts(Rule::survives), counts(Rule::born))
+}
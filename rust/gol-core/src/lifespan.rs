@@ -0,0 +1,419 @@
+use crate::census::{islands, Connectivity, Island};
+use crate::cycle::{Cycle, CycleDetector};
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+use crate::sparse_world::SparseWorld;
+use crate::universe::{BoundingBox, Universe};
+use crate::world::{Settings, WorldError};
+use std::collections::HashSet;
+use std::mem;
+use std::ops::Range;
+
+// Spaceships are taken off the board once they are this many cells
+// ahead of everything else, in the directions they travel
+const GAP: i64 = 8;
+
+// Islands with more cells than this aren't checked for escaping, as no
+// common spaceship is that big
+const MAX_ESCAPING: usize = 32;
+
+// Generations between looks for escaping spaceships. Some phases of a
+// spaceship come apart into more than one island, as the lightweight
+// spaceship does every other generation, so this is odd to look at
+// every phase of the common period 2 and 4 ones in turn
+const SCAN: u64 = 7;
+
+// Directions a spaceship can be ahead of everything else in
+#[rustfmt::skip]
+const DIRECTIONS: [(i64, i64); 8] = [
+  (-1, -1), (0, -1), (1, -1),
+  (-1, 0),           (1, 0),
+  (-1, 1),  (0, 1),  (1, 1),
+];
+
+/// How a pattern played out on the unbounded plane until it stabilised,
+/// such as how long a methuselah lives.
+///
+/// Spaceships that escape, such as the gliders a methuselah throws off,
+/// never settle down, so they don't count towards the pattern settling
+/// or towards what is left of it. Everything else has stabilised at the
+/// first generation from which it repeats itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lifespan {
+  /// The cycle the pattern settled into, which starts at the generation
+  /// it stabilised, or `None` when it hadn't by the last generation
+  /// measured. Patterns that die out settle into an empty still life.
+  pub cycle: Option<Cycle>,
+  /// The largest population, escaping spaceships included.
+  pub peak_population: u64,
+  /// The first generation with the largest population.
+  pub peak_generation: u64,
+  /// The generation the pattern stabilised at, or the last one
+  /// measured.
+  pub final_generation: u64,
+  /// Living cells at the final generation, escaping spaceships left
+  /// out.
+  pub final_population: u64,
+  /// Box around those cells, or `None` when none are left.
+  pub bounding_box: Option<BoundingBox>,
+  /// The spaceships that escaped, as they were when they were taken off
+  /// the board, oldest first.
+  pub escaped: Vec<Island>,
+}
+
+// A spaceship taken off the board, and every phase of it, so where it
+// was or would be at any generation can be worked out
+struct Escape {
+  island: Island,
+  generation: u64,
+  phases: Vec<Vec<(i64, i64)>>,
+}
+
+impl Lifespan {
+  /// Plays a pattern from generation 0 on an unbounded plane until it
+  /// stabilises, or for `max_generations` generations at most, and
+  /// measures it.
+  ///
+  /// Spaceships are taken off the board once they are well ahead of
+  /// everything else, so they can't keep the pattern from repeating. The
+  /// pattern is then played again with them in place, to find the
+  /// generation it stabilised at rather than the one the last of them
+  /// was taken off at.
+  pub fn measure(
+    pattern: &Pattern,
+    rule: Rule,
+    max_generations: u64,
+  ) -> Result<Lifespan, WorldError> {
+    let mut world = Lifespan::world(pattern, rule)?;
+    let mut detector = CycleDetector::new();
+    let mut escaped = Vec::new();
+    let mut escapes = Escapes::default();
+    let period = loop {
+      if let Some(cycle) = detector.observe(&world) {
+        break Some(cycle.period);
+      }
+      if world.tick() >= max_generations {
+        break None;
+      }
+      world._tick();
+      // Removing cells changes the world's history, so the detector
+      // starts again from here
+      let removed = escapes.remove(&mut world, rule)?;
+      if !removed.is_empty() {
+        detector = CycleDetector::new();
+      }
+      for island in removed {
+        escaped.push(Escape::new(island, world.tick(), rule));
+      }
+    };
+    let end = world.tick() as usize;
+
+    // Played again with nothing removed, every generation's hash has
+    // the spaceships taken out by subtracting theirs, for as long as
+    // they can be found flying where they were going to be
+    let mut world = Lifespan::world(pattern, rule)?;
+    let mut hashes = Vec::with_capacity(end + 1);
+    let mut populations = Vec::with_capacity(end + 1);
+    let mut found = vec![Vec::with_capacity(end + 1); escaped.len()];
+    loop {
+      let cells: Vec<(i64, i64)> = world.live_cells().collect();
+      hashes.push(CycleDetector::hash_cells(&cells));
+      populations.push(cells.len() as u64);
+      for (escape, found) in escaped.iter().zip(&mut found) {
+        let cells = escape.cells_at(world.tick());
+        let flying = cells.iter().all(|&(x, y)| world.alive_at(x, y));
+        found.push(flying.then(|| CycleDetector::hash_cells(&cells)));
+      }
+      if world.tick() as usize >= end {
+        break;
+      }
+      world._tick();
+    }
+
+    let mut flights = Vec::with_capacity(escaped.len());
+    for (escape, found) in escaped.iter().zip(&found) {
+      let flight = Lifespan::flight(escape, found);
+      for generation in flight.clone() {
+        let hash = found[generation].expect("flights only cover generations it was found");
+        hashes[generation] = hashes[generation].wrapping_sub(hash);
+      }
+      flights.push(flight);
+    }
+
+    // The cycle goes back as far as the generations keep matching the
+    // ones a period later
+    let cycle = period.map(|period| {
+      let period = period as usize;
+      let mut start = end - period;
+      while start > 0 && hashes[start - 1] == hashes[start - 1 + period] {
+        start -= 1;
+      }
+      Cycle {
+        start: start as u64,
+        period: period as u64,
+      }
+    });
+
+    let (peak_generation, &peak_population) = populations
+      .iter()
+      .enumerate()
+      .rev()
+      .max_by_key(|&(_, population)| population)
+      .expect("at least one generation is played");
+
+    // Played a last time to the final generation, for what is left
+    let final_generation = cycle.map_or(end as u64, |cycle| cycle.start);
+    let mut world = Lifespan::world(pattern, rule)?;
+    while world.tick() < final_generation {
+      world._tick();
+    }
+    for (escape, flight) in escaped.iter().zip(&flights) {
+      if flight.contains(&(final_generation as usize)) {
+        for (x, y) in escape.cells_at(final_generation) {
+          world.set_alive(x, y, false);
+        }
+      }
+    }
+
+    Ok(Lifespan {
+      cycle,
+      peak_population,
+      peak_generation: peak_generation as u64,
+      final_generation,
+      final_population: world.population(),
+      bounding_box: world.bounding_box(),
+      escaped: escaped.into_iter().map(|escape| escape.island).collect(),
+    })
+  }
+
+  fn world(pattern: &Pattern, rule: Rule) -> Result<SparseWorld, WorldError> {
+    let settings = Settings {
+      rule,
+      density: 0.0,
+      ..Settings::default()
+    };
+    let mut world = SparseWorld::with_settings(0, 0, settings)?;
+    for &(x, y) in &pattern.cells {
+      world.set_alive(x, y, true);
+    }
+    Ok(world)
+  }
+
+  // The generations around the one a spaceship was taken off at that it
+  // can be found flying in, without a break
+  fn flight(escape: &Escape, found: &[Option<u64>]) -> Range<usize> {
+    let taken = escape.generation as usize;
+    let start = (0..=taken)
+      .rev()
+      .find(|&generation| found[generation].is_none())
+      .map_or(0, |generation| generation + 1);
+    let end = (taken..found.len())
+      .find(|&generation| found[generation].is_none())
+      .unwrap_or(found.len());
+    start..end
+  }
+}
+
+impl Escape {
+  fn new(island: Island, generation: u64, rule: Rule) -> Escape {
+    let period = island.period.expect("spaceships have a period");
+    let settings = Settings {
+      rule,
+      density: 0.0,
+      ..Settings::default()
+    };
+    let mut world =
+      SparseWorld::with_settings(0, 0, settings).expect("the rule already played the pattern");
+    for &(x, y) in &island.cells {
+      world.set_alive(x, y, true);
+    }
+
+    let mut phases = Vec::with_capacity(period as usize);
+    for _ in 0..period {
+      phases.push(world.live_cells().collect());
+      world._tick();
+    }
+    Escape {
+      island,
+      generation,
+      phases,
+    }
+  }
+
+  // Which way along each axis the spaceship travels
+  fn heading(&self) -> (i64, i64) {
+    let velocity = self.island.velocity.expect("spaceships have a velocity");
+    (velocity.dx.signum(), velocity.dy.signum())
+  }
+
+  // Whole periods move the spaceship by its velocity, backwards as well
+  // as forwards
+  fn cells_at(&self, generation: u64) -> Vec<(i64, i64)> {
+    let velocity = self.island.velocity.expect("spaceships have a velocity");
+    let offset = generation as i64 - self.generation as i64;
+    let period = self.phases.len() as i64;
+    let (phase, periods) = (offset.rem_euclid(period), offset.div_euclid(period));
+    self.phases[phase as usize]
+      .iter()
+      .map(|&(x, y)| (x + periods * velocity.dx, y + periods * velocity.dy))
+      .collect()
+  }
+}
+
+/// Spaceships escaping off an unbounded universe, found as it plays.
+///
+/// A spaceship is only taken off once every other cell is well behind
+/// it along the way it travels, and has stayed so for a full period
+/// without anything behind it gaining on it, so nothing can catch up
+/// with it.
+//
+// Islands that are ahead of everything else but turn out not to be
+// spaceships are remembered in judged, so a still life left out on its
+// own isn't played out again. Finding islands takes a pass over every
+// cell, so it is only done every SCAN generations
+#[derive(Default)]
+pub(crate) struct Escapes {
+  judged: HashSet<Vec<(i64, i64)>>,
+  watched: Vec<Watched>,
+  scanned: Option<u64>,
+}
+
+// A spaceship ahead of everything else, and how far ahead it was when
+// it was first seen there
+struct Watched {
+  escape: Escape,
+  gap: i64,
+}
+
+impl Escapes {
+  /// Takes the spaceships that have escaped off the universe, and
+  /// returns them as they were when they were taken off.
+  pub(crate) fn remove(
+    &mut self,
+    world: &mut dyn Universe,
+    rule: Rule,
+  ) -> Result<Vec<Island>, WorldError> {
+    let tick = world.tick();
+    if self.scanned.is_some_and(|scanned| tick < scanned + SCAN) {
+      return Ok(Vec::new());
+    }
+    self.scanned = Some(tick);
+
+    let cells = world.live_cells();
+    let mut islands: Vec<Vec<(i64, i64)>> = islands(&cells, Connectivity::Moore);
+    for island in &mut islands {
+      island.sort_unstable();
+    }
+
+    // Ships watched for a period are taken off when they are still
+    // where they were heading and nothing has gained on them, and
+    // watched again from here when something has. They are looked for
+    // cell by cell, as some phases of a ship are more than one island
+    let alive: HashSet<(i64, i64)> = cells.iter().copied().collect();
+    let mut removed = Vec::new();
+    let mut watched = Vec::new();
+    let mut watching = HashSet::new();
+    for ship in mem::take(&mut self.watched) {
+      let mut expected = ship.escape.cells_at(tick);
+      expected.sort_unstable();
+      if !expected.iter().all(|cell| alive.contains(cell)) {
+        continue;
+      }
+      watching.extend(expected.iter().copied());
+      if tick < ship.escape.generation + ship.escape.phases.len() as u64 {
+        watched.push(ship);
+        continue;
+      }
+
+      let gap = gap(&cells, &expected, ship.escape.heading());
+      if gap >= ship.gap {
+        for &(x, y) in &expected {
+          world.set_alive(x, y, false)?;
+        }
+        removed.push(Island {
+          cells: expected,
+          ..ship.escape.island
+        });
+      } else if gap > GAP {
+        let island = Island {
+          cells: expected,
+          ..ship.escape.island
+        };
+        watched.push(Watched {
+          escape: Escape::new(island, tick, rule),
+          gap,
+        });
+      }
+    }
+
+    for island in islands {
+      if island.len() > MAX_ESCAPING
+        || self.judged.contains(&island)
+        || watching.contains(&island[0])
+      {
+        continue;
+      }
+      let ahead = |heading| ahead(&cells, &island, heading);
+      if !DIRECTIONS.into_iter().any(ahead) {
+        continue;
+      }
+
+      let candidate = Island::new(island.clone(), rule);
+      let heading = candidate
+        .velocity
+        .map(|velocity| (velocity.dx.signum(), velocity.dy.signum()));
+      if !heading.is_some_and(ahead) {
+        self.judged.insert(island);
+        continue;
+      }
+      let escape = Escape::new(candidate, tick, rule);
+      watched.push(Watched {
+        gap: gap(&cells, &island, escape.heading()),
+        escape,
+      });
+    }
+
+    self.watched = watched;
+    Ok(removed)
+  }
+}
+
+// How far behind the island the nearest cell outside it is
+fn gap(cells: &[(i64, i64)], island: &[(i64, i64)], heading: (i64, i64)) -> i64 {
+  behind(cells, island, heading).min().unwrap_or(i64::MAX)
+}
+
+// Whether every cell outside the island is more than GAP cells behind
+// it, which stops at the first one that isn't
+fn ahead(cells: &[(i64, i64)], island: &[(i64, i64)], heading: (i64, i64)) -> bool {
+  behind(cells, island, heading).all(|distance| distance > GAP)
+}
+
+// How far behind the island each cell outside it is, along whichever of
+// the axes the island is heading along it is furthest behind on
+fn behind<'a>(
+  cells: &'a [(i64, i64)],
+  island: &'a [(i64, i64)],
+  (dx, dy): (i64, i64),
+) -> impl Iterator<Item = i64> + 'a {
+  let min_x = island.iter().map(|&(x, _)| x).min().unwrap_or(0);
+  let min_y = island.iter().map(|&(_, y)| y).min().unwrap_or(0);
+  let max_x = island.iter().map(|&(x, _)| x).max().unwrap_or(0);
+  let max_y = island.iter().map(|&(_, y)| y).max().unwrap_or(0);
+
+  cells
+    .iter()
+    .filter(|cell| island.binary_search(cell).is_err())
+    .map(move |&(x, y)| {
+      [
+        (dx > 0).then(|| min_x - x),
+        (dx < 0).then(|| x - max_x),
+        (dy > 0).then(|| min_y - y),
+        (dy < 0).then(|| y - max_y),
+      ]
+      .into_iter()
+      .flatten()
+      .max()
+      .unwrap_or(i64::MIN)
+    })
+}
@@ -0,0 +1,122 @@
+use crate::rule::Rule;
+use crate::universe::{BoundingBox, Universe};
+use crate::world::WorldError;
+
+/// A pattern of living cells, as read from or written to a pattern
+/// file, independent of any world it is placed in.
+///
+/// Cells are relative to the pattern's top left corner, so they run
+/// from `(0, 0)` to `(columns - 1, rows - 1)`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pattern {
+  /// Name of the pattern, from an `#N` line.
+  pub name: Option<String>,
+  /// Who found or wrote the pattern, from an `#O` line.
+  pub author: Option<String>,
+  /// Free text comments, one per line.
+  pub comments: Vec<String>,
+  /// Rule the pattern was made for, when the file names one.
+  pub rule: Option<Rule>,
+  /// Number of columns the pattern spans.
+  pub columns: i64,
+  /// Number of rows the pattern spans.
+  pub rows: i64,
+  /// Locations of the living cells.
+  pub cells: Vec<(i64, i64)>,
+  /// Generation the pattern was captured at, when the file records it.
+  pub generation: u64,
+}
+
+impl Pattern {
+  /// Most living cells an RLE file may expand to, as every one of them
+  /// is a separate entry in `cells` however short the run it came from.
+  pub const MAX_CELLS: usize = 1 << 24;
+
+  /// Creates a pattern from living cells anywhere on the plane, cropped
+  /// so its top left corner is the top left of their bounding box.
+  pub fn new<I: IntoIterator<Item = (i64, i64)>>(cells: I) -> Pattern {
+    let cells: Vec<(i64, i64)> = cells.into_iter().collect();
+    let Some(&(x, y)) = cells.first() else {
+      return Pattern::default();
+    };
+
+    let mut bounding_box = BoundingBox {
+      min_x: x,
+      min_y: y,
+      max_x: x,
+      max_y: y,
+    };
+    for &(x, y) in &cells {
+      bounding_box.include(x, y);
+    }
+
+    Pattern {
+      columns: bounding_box.width() as i64,
+      rows: bounding_box.height() as i64,
+      cells: cells
+        .into_iter()
+        .map(|(x, y)| (x - bounding_box.min_x, y - bounding_box.min_y))
+        .collect(),
+      ..Pattern::default()
+    }
+  }
+
+  /// Captures the living cells of a universe, with the rule it plays
+  /// and its generation, as a pattern.
+  pub fn from_universe(universe: &dyn Universe) -> Pattern {
+    Pattern {
+      rule: Some(universe.rule()),
+      generation: universe.tick(),
+      ..Pattern::new(universe.live_cells())
+    }
+  }
+
+  /// Brings the pattern's cells to life in a universe, with its top
+  /// left corner at `(x, y)`. Nothing is changed when the pattern
+  /// doesn't fit, and the universe keeps its own generation.
+  pub fn place(&self, universe: &mut dyn Universe, x: i64, y: i64) -> Result<(), WorldError> {
+    self.check_fits(x, y, |x, y| universe.in_bounds(x, y))?;
+    for &(rel_x, rel_y) in &self.cells {
+      universe.set_alive(x + rel_x, y + rel_y, true)?;
+    }
+    Ok(())
+  }
+
+  // Rows of cells, dead or alive, from the top left corner to the
+  // bottom right, for the formats that draw every location
+  pub(crate) fn grid(&self) -> Vec<Vec<bool>> {
+    let mut grid = vec![vec![false; self.columns.max(0) as usize]; self.rows.max(0) as usize];
+    for &(x, y) in &self.cells {
+      if x >= 0 && y >= 0 && x < self.columns && y < self.rows {
+        grid[y as usize][x as usize] = true;
+      }
+    }
+    grid
+  }
+
+  // Both corners have to be inside for a rectangular world to hold
+  // the pattern, and an empty pattern fits anywhere
+  pub(crate) fn check_fits<F: Fn(i64, i64) -> bool>(
+    &self,
+    x: i64,
+    y: i64,
+    in_bounds: F,
+  ) -> Result<(), WorldError> {
+    if self.columns == 0 || self.rows == 0 {
+      return Ok(());
+    }
+
+    let far_corner = x
+      .checked_add(self.columns - 1)
+      .zip(y.checked_add(self.rows - 1));
+    match far_corner {
+      Some((far_x, far_y)) if in_bounds(x, y) && in_bounds(far_x, far_y) => Ok(()),
+      _ => Err(WorldError::PatternDoesNotFit {
+        x,
+        y,
+        columns: self.columns,
+        rows: self.rows,
+      }),
+    }
+  }
+}
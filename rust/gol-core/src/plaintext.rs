@@ -0,0 +1,80 @@
+use crate::pattern::Pattern;
+use crate::world::WorldError;
+
+impl Pattern {
+  /// Parses a LifeWiki plaintext (`.cells`) pattern.
+  ///
+  /// Lines starting with `!` are comments, where `!Name:` and
+  /// `!Author:` fill in the name and author. Every other line is a row,
+  /// `O` (or `*`) for alive and `.` for dead, and rows may be shorter
+  /// than the widest one:
+  ///
+  /// ```text
+  /// !Name: Glider
+  /// .O
+  /// ..O
+  /// OOO
+  /// ```
+  pub fn from_plaintext(text: &str) -> Result<Pattern, WorldError> {
+    let mut pattern = Pattern::default();
+    let mut rows_with_text = 0;
+
+    for (i, line) in text.lines().enumerate() {
+      let line = line.trim_end();
+      if let Some(comment) = line.strip_prefix('!') {
+        if let Some(name) = comment.strip_prefix("Name:") {
+          pattern.name = Some(name.trim().to_string());
+        } else if let Some(author) = comment.strip_prefix("Author:") {
+          pattern.author = Some(author.trim().to_string());
+        } else {
+          pattern.comments.push(comment.trim().to_string());
+        }
+        continue;
+      }
+
+      let y = pattern.rows;
+      for (x, char) in line.chars().enumerate() {
+        match char {
+          'O' | '*' => pattern.cells.push((x as i64, y)),
+          '.' => {}
+          char => {
+            return Err(WorldError::InvalidPattern {
+              line: i + 1,
+              reason: format!("unexpected character {:?}", char),
+            })
+          }
+        }
+      }
+      pattern.columns = pattern.columns.max(line.chars().count() as i64);
+      pattern.rows += 1;
+      if !line.is_empty() {
+        rows_with_text = pattern.rows;
+      }
+    }
+
+    // Blank lines at the end are the file's, not rows of the pattern
+    pattern.rows = rows_with_text;
+    Ok(pattern)
+  }
+
+  /// Writes the pattern as LifeWiki plaintext (`.cells`), with every
+  /// row padded to the full width of the pattern.
+  pub fn to_plaintext(&self) -> String {
+    let mut plaintext = String::new();
+    if let Some(name) = &self.name {
+      plaintext += &format!("!Name: {}\n", name);
+    }
+    if let Some(author) = &self.author {
+      plaintext += &format!("!Author: {}\n", author);
+    }
+    for comment in &self.comments {
+      plaintext += &format!("!{}\n", comment);
+    }
+
+    for row in self.grid() {
+      plaintext.extend(row.iter().map(|&alive| if alive { 'O' } else { '.' }));
+      plaintext.push('\n');
+    }
+    plaintext
+  }
+}
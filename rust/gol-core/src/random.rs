@@ -0,0 +1,57 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The SplitMix64 generator used to fill random soups, chosen because
+/// it is tiny and easy to port, so a seed gives the same soup on every
+/// platform and in any implementation that copies it.
+///
+/// Each number adds `0x9E3779B97F4A7C15` to the 64 bit state, then
+/// mixes a copy of it:
+///
+/// ```text
+/// z = (z ^ (z >> 30)) * 0xBF58476D1CE4E5B9
+/// z = (z ^ (z >> 27)) * 0x94D049BB133111EB
+/// z ^ (z >> 31)
+/// ```
+///
+/// A soup draws one number per location, row by row from `(0, 0)` to
+/// `(width, height)`, and the cell is alive when the top 53 bits as a
+/// fraction of `2^53` are below the density.
+#[derive(Debug, Clone)]
+pub struct Random {
+  state: u64,
+}
+
+impl Random {
+  /// Creates a generator that starts from a seed.
+  pub fn new(seed: u64) -> Random {
+    Random { state: seed }
+  }
+
+  /// Returns a seed taken from the clock, for soups that don't need to
+  /// be reproduced.
+  pub fn clock_seed() -> u64 {
+    SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|time| time.as_nanos() as u64)
+      .unwrap_or(0)
+  }
+
+  /// Returns the next 64 random bits.
+  pub fn next_u64(&mut self) -> u64 {
+    self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = self.state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+  }
+
+  /// Returns a number from `0.0` up to, but not including, `1.0`.
+  pub fn next_f64(&mut self) -> f64 {
+    (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+  }
+
+  /// Returns whether the next cell of a soup with this density is alive.
+  pub fn next_alive(&mut self, density: f64) -> bool {
+    self.next_f64() < density
+  }
+}
@@ -0,0 +1,120 @@
+use crate::universe::{BoundingBox, Universe};
+
+/// Turns a universe into text, so drivers can pick how worlds are drawn
+/// without caring which backend plays them.
+pub trait Renderer {
+  /// Draws the cells in a region of the universe, which may reach past
+  /// its edges, where every cell is dead.
+  fn render_region(&self, universe: &dyn Universe, region: BoundingBox) -> String;
+
+  /// Draws the region `render` covers, from the universe's viewport to
+  /// `width` columns and `height` rows past it.
+  fn render(&self, universe: &dyn Universe) -> String {
+    self.render_region(universe, viewport(universe))
+  }
+}
+
+/// The text every implementation prints, `o` for alive and space for
+/// dead, with one line per row.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextRenderer;
+
+impl Renderer for TextRenderer {
+  fn render_region(&self, universe: &dyn Universe, region: BoundingBox) -> String {
+    render_blocks(universe, region, 1, 1, |alive| match alive[0] {
+      true => 'o',
+      false => ' ',
+    })
+  }
+
+  // Each backend renders its own cells the fastest way it can
+  fn render(&self, universe: &dyn Universe) -> String {
+    universe.render()
+  }
+}
+
+/// Packs two rows into each line with the half block characters `▀`,
+/// `▄` and `█`, so twice as many rows fit in a terminal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HalfBlockRenderer;
+
+impl Renderer for HalfBlockRenderer {
+  fn render_region(&self, universe: &dyn Universe, region: BoundingBox) -> String {
+    render_blocks(universe, region, 1, 2, |alive| match (alive[0], alive[1]) {
+      (true, true) => '█',
+      (true, false) => '▀',
+      (false, true) => '▄',
+      (false, false) => ' ',
+    })
+  }
+}
+
+/// Packs two columns and four rows into each character with the braille
+/// patterns from U+2800, so eight times as many cells fit in a
+/// terminal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BrailleRenderer;
+
+impl BrailleRenderer {
+  // The dot each cell of a 2x4 block raises, column by column. The
+  // bottom row was added to braille later, so it has the high bits
+  const DOTS: [u32; 8] = [0x01, 0x02, 0x04, 0x40, 0x08, 0x10, 0x20, 0x80];
+}
+
+impl Renderer for BrailleRenderer {
+  fn render_region(&self, universe: &dyn Universe, region: BoundingBox) -> String {
+    render_blocks(universe, region, 2, 4, |alive| {
+      let dots = (0..8)
+        .filter(|&i| alive[i])
+        .fold(0, |dots, i| dots | BrailleRenderer::DOTS[i]);
+      // Blank patterns are written as spaces, like dead cells elsewhere
+      match dots {
+        0 => ' ',
+        dots => char::from_u32(0x2800 + dots).unwrap_or(' '),
+      }
+    })
+  }
+}
+
+// The region `render` covers
+pub(crate) fn viewport(universe: &dyn Universe) -> BoundingBox {
+  let (x, y) = universe.viewport();
+  BoundingBox {
+    min_x: x,
+    min_y: y,
+    max_x: x.saturating_add(universe.width()),
+    max_y: y.saturating_add(universe.height()),
+  }
+}
+
+// Draws blocks of `columns` by `rows` cells as one character each. The
+// glyph gets the block's cells column by column, and cells past the
+// edge of the region are dead
+fn render_blocks<F: Fn(&[bool]) -> char>(
+  universe: &dyn Universe,
+  region: BoundingBox,
+  columns: i64,
+  rows: i64,
+  glyph: F,
+) -> String {
+  let mut alive = vec![false; (columns * rows) as usize];
+  let mut rendering = String::new();
+
+  for y in (region.min_y..=region.max_y).step_by(rows as usize) {
+    for x in (region.min_x..=region.max_x).step_by(columns as usize) {
+      for column in 0..columns {
+        for row in 0..rows {
+          let cell_x = x.checked_add(column).filter(|&x| x <= region.max_x);
+          let cell_y = y.checked_add(row).filter(|&y| y <= region.max_y);
+          alive[(column * rows + row) as usize] = match (cell_x, cell_y) {
+            (Some(x), Some(y)) => universe.alive_at(x, y),
+            _ => false,
+          };
+        }
+      }
+      rendering.push(glyph(&alive));
+    }
+    rendering.push('\n');
+  }
+  rendering
+}
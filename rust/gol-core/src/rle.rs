@@ -0,0 +1,256 @@
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+use crate::world::WorldError;
+use std::collections::BTreeMap;
+
+// Golly and the LifeWiki wrap RLE bodies at 70 characters
+const LINE_LENGTH: usize = 70;
+
+impl Pattern {
+  /// Parses a Run Length Encoded (`.rle`) pattern.
+  ///
+  /// `#N`, `#O` and `#C` lines before the `x = , y = , rule =` header
+  /// fill in the name, author and comments. In the body a count may
+  /// come before each tag, `b` is a run of dead cells, `o` a run of
+  /// living ones, `$` ends a row and `!` ends the pattern:
+  ///
+  /// ```text
+  /// #N Glider
+  /// x = 3, y = 3, rule = B3/S23
+  /// bob$2bo$3o!
+  /// ```
+  ///
+  /// Files whose runs add up to more than [`Pattern::MAX_CELLS`] living
+  /// cells are rejected.
+  pub fn from_rle(text: &str) -> Result<Pattern, WorldError> {
+    let mut pattern = Pattern::default();
+    let mut lines = text
+      .lines()
+      .enumerate()
+      .map(|(i, line)| (i + 1, line.trim()));
+
+    // Comments and blank lines lead up to the header
+    let header = loop {
+      let Some((number, line)) = lines.next() else {
+        return Err(invalid(
+          text.lines().count().max(1),
+          "missing the x = , y = header",
+        ));
+      };
+
+      if let Some(comment) = line.strip_prefix('#') {
+        read_comment(&mut pattern, comment)?;
+      } else if !line.is_empty() {
+        break (number, line);
+      }
+    };
+    read_header(&mut pattern, header)?;
+
+    let mut count: Option<i64> = None;
+    let (mut x, mut y): (i64, i64) = (0, 0);
+    'body: for (number, line) in lines {
+      // Some tools write their comments after the header
+      if let Some(comment) = line.strip_prefix('#') {
+        read_comment(&mut pattern, comment)?;
+        continue;
+      }
+
+      for char in line.chars() {
+        if let Some(digit) = char.to_digit(10) {
+          count = count
+            .unwrap_or(0)
+            .checked_mul(10)
+            .and_then(|count| count.checked_add(digit as i64));
+          if count.is_none() {
+            return Err(invalid(number, "run count is too large"));
+          }
+          continue;
+        }
+
+        let run = count.take().unwrap_or(1);
+        match char {
+          'b' | '.' => x = x.saturating_add(run),
+          '$' => {
+            x = 0;
+            y = y.saturating_add(run);
+          }
+          '!' => break 'body,
+          // Any other state of a multi-state rule counts as alive
+          char if char.is_ascii_alphabetic() => {
+            if x.saturating_add(run) > pattern.columns || y >= pattern.rows {
+              return Err(WorldError::CellOutsidePattern {
+                line: number,
+                x: x.saturating_add(run.max(1) - 1),
+                y,
+                columns: pattern.columns,
+                rows: pattern.rows,
+              });
+            }
+            // The header can declare any size, so a short file could
+            // otherwise ask for billions of cells
+            if run as u64 > (Pattern::MAX_CELLS - pattern.cells.len()) as u64 {
+              return Err(invalid(
+                number,
+                &format!("more than {} living cells", Pattern::MAX_CELLS),
+              ));
+            }
+            pattern.cells.extend((x..x + run).map(|x| (x, y)));
+            x += run;
+          }
+          char if char.is_whitespace() => {}
+          char => return Err(invalid(number, &format!("unexpected character {:?}", char))),
+        }
+      }
+    }
+
+    Ok(pattern)
+  }
+
+  /// Writes the pattern as Run Length Encoded (`.rle`) text, with the
+  /// name, author and comments as `#N`, `#O` and `#C` lines. Patterns
+  /// without a rule are written as Conway's `B3/S23`.
+  pub fn to_rle(&self) -> String {
+    let mut rle = String::new();
+    if let Some(name) = &self.name {
+      rle += &format!("#N {}\n", name);
+    }
+    if let Some(author) = &self.author {
+      rle += &format!("#O {}\n", author);
+    }
+    for comment in &self.comments {
+      rle += &format!("#C {}\n", comment);
+    }
+    rle += &format!(
+      "x = {}, y = {}, rule = {}\n",
+      self.columns,
+      self.rows,
+      self.rule.unwrap_or_default()
+    );
+
+    // Sorting the cells into rows turns each row into runs, and dead
+    // cells after the last living one in a row are left out
+    let mut rows: BTreeMap<i64, Vec<i64>> = BTreeMap::new();
+    for &(x, y) in &self.cells {
+      rows.entry(y).or_default().push(x);
+    }
+
+    let mut tags = Vec::new();
+    let mut last_y = 0;
+    for (y, mut columns) in rows {
+      columns.sort_unstable();
+      columns.dedup();
+
+      if y > last_y {
+        tags.push(run(y - last_y, '$'));
+        last_y = y;
+      }
+
+      let mut x = 0;
+      let mut columns = columns.into_iter().peekable();
+      while let Some(start) = columns.next() {
+        let mut end = start + 1;
+        while columns.next_if_eq(&end).is_some() {
+          end += 1;
+        }
+        if start > x {
+          tags.push(run(start - x, 'b'));
+        }
+        tags.push(run(end - start, 'o'));
+        x = end;
+      }
+    }
+    tags.push("!".to_string());
+
+    // Runs are never split across lines
+    let mut line = String::new();
+    for tag in tags {
+      if line.len() + tag.len() > LINE_LENGTH {
+        rle += &line;
+        rle.push('\n');
+        line.clear();
+      }
+      line += &tag;
+    }
+    rle += &line;
+    rle.push('\n');
+    rle
+  }
+}
+
+fn read_comment(pattern: &mut Pattern, comment: &str) -> Result<(), WorldError> {
+  let (kind, value) = comment.split_at(comment.len().min(1));
+  let value = value.trim().to_string();
+  match kind {
+    "N" => pattern.name = Some(value),
+    "O" => pattern.author = Some(value),
+    "C" | "c" => pattern.comments.push(value),
+    "r" => pattern.rule = Some(Rule::parse(&value)?),
+    // #P and #R place the pattern in other tools, which we leave to
+    // the caller
+    _ => {}
+  }
+  Ok(())
+}
+
+// Reads `x = 3, y = 3, rule = B3/S23`, where the rule is optional and
+// may carry a `:T` suffix describing Golly's bounded grids
+fn read_header(pattern: &mut Pattern, (number, line): (usize, &str)) -> Result<(), WorldError> {
+  let (mut columns, mut rows) = (None, None);
+  let mut rest = line;
+  while !rest.is_empty() {
+    // The rule comes last and its suffix may hold commas of its own
+    let (field, remainder) = if rest.trim_start().starts_with("rule") {
+      (rest, "")
+    } else {
+      rest.split_once(',').unwrap_or((rest, ""))
+    };
+    rest = remainder;
+
+    let Some((name, value)) = field.split_once('=') else {
+      return Err(invalid(
+        number,
+        &format!("expected name = value, not {:?}", field.trim()),
+      ));
+    };
+    let value = value.trim();
+    let size = || match value.parse::<i64>() {
+      Ok(size) if size >= 0 => Ok(size),
+      _ => Err(invalid(number, &format!("{:?} is not a size", value))),
+    };
+
+    match name.trim() {
+      "x" => columns = Some(size()?),
+      "y" => rows = Some(size()?),
+      "rule" => {
+        let rule = value.split(':').next().unwrap_or_default();
+        pattern.rule = Some(Rule::parse(rule)?);
+      }
+      // Other tools add fields of their own, which we don't need
+      _ => {}
+    }
+  }
+
+  match (columns, rows) {
+    (Some(columns), Some(rows)) => {
+      pattern.columns = columns;
+      pattern.rows = rows;
+      Ok(())
+    }
+    _ => Err(invalid(number, "the header needs both x and y")),
+  }
+}
+
+fn run(length: i64, tag: char) -> String {
+  if length == 1 {
+    tag.to_string()
+  } else {
+    format!("{}{}", length, tag)
+  }
+}
+
+fn invalid(line: usize, reason: &str) -> WorldError {
+  WorldError::InvalidPattern {
+    line,
+    reason: reason.to_string(),
+  }
+}
@@ -0,0 +1,115 @@
+use crate::world::WorldError;
+use std::fmt;
+use std::str::FromStr;
+
+/// Birth and survival conditions of a Life-like automaton.
+///
+/// Rules are written as rulestrings, either `B3/S23` (birth first) or
+/// the older `23/3` (survival first). Bit `n` of each mask is set when
+/// a cell with `n` living neighbours is born or survives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rule {
+  birth: u16,
+  survival: u16,
+}
+
+impl Rule {
+  /// Conway's Game of Life, `B3/S23`.
+  pub const CONWAY: Rule = Rule {
+    birth: 1 << 3,
+    survival: 1 << 2 | 1 << 3,
+  };
+
+  /// Creates a rule from the neighbour counts that cause birth and
+  /// survival. Counts above 8 are ignored.
+  pub fn new(birth: &[usize], survival: &[usize]) -> Rule {
+    let mask = |counts: &[usize]| {
+      counts
+        .iter()
+        .filter(|&&count| count <= 8)
+        .fold(0, |mask, count| mask | 1 << count)
+    };
+
+    Rule {
+      birth: mask(birth),
+      survival: mask(survival),
+    }
+  }
+
+  /// Parses a rulestring such as `B36/S23`, `S23/B3` or `23/3`.
+  pub fn parse(rulestring: &str) -> Result<Rule, WorldError> {
+    let invalid = || WorldError::InvalidRule {
+      rule: rulestring.to_string(),
+    };
+
+    let lower = rulestring.trim().to_ascii_lowercase();
+    let (first, second) = match lower.split_once('/') {
+      Some(parts) => parts,
+      // Golly also accepts B3S23 without the slash
+      None => match lower.find('s') {
+        Some(index) if lower.starts_with('b') => lower.split_at(index),
+        _ => return Err(invalid()),
+      },
+    };
+
+    let (birth, survival) = match (first.chars().next(), second.chars().next()) {
+      (Some('b'), Some('s')) => (&first[1..], &second[1..]),
+      (Some('s'), Some('b')) => (&second[1..], &first[1..]),
+      // Without letters the older notation lists survival first
+      _ => (second, first),
+    };
+
+    let mask = |counts: &str| {
+      counts
+        .chars()
+        .try_fold(0, |mask, count| match count.to_digit(10) {
+          Some(count) if count <= 8 => Ok(mask | 1 << count),
+          _ => Err(invalid()),
+        })
+    };
+
+    Ok(Rule {
+      birth: mask(birth)?,
+      survival: mask(survival)?,
+    })
+  }
+
+  /// Returns whether a dead cell with this many living neighbours is born.
+  pub fn born(&self, alive_neighbours: usize) -> bool {
+    alive_neighbours <= 8 && self.birth & 1 << alive_neighbours != 0
+  }
+
+  /// Returns whether a living cell with this many living neighbours
+  /// stays alive.
+  pub fn survives(&self, alive_neighbours: usize) -> bool {
+    alive_neighbours <= 8 && self.survival & 1 << alive_neighbours != 0
+  }
+}
+
+impl Default for Rule {
+  fn default() -> Rule {
+    Rule::CONWAY
+  }
+}
+
+impl FromStr for Rule {
+  type Err = WorldError;
+
+  fn from_str(rulestring: &str) -> Result<Rule, WorldError> {
+    Rule::parse(rulestring)
+  }
+}
+
+impl fmt::Display for Rule {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "B")?;
+    for count in (0..=8).filter(|&count| self.born(count)) {
+      write!(f, "{}", count)?;
+    }
+    write!(f, "/S")?;
+    for count in (0..=8).filter(|&count| self.survives(count)) {
+      write!(f, "{}", count)?;
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,428 @@
+use crate::census::{islands, Census, Connectivity, Island};
+use crate::cycle::CycleDetector;
+use crate::lifespan::Escapes;
+use crate::random::Random;
+use crate::rule::Rule;
+use crate::sparse_world::SparseWorld;
+use crate::universe::Universe;
+use crate::workers;
+use crate::world::{Settings, World, WorldError};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+// Spaceships are recognised once they come this close to the edge, and
+// taken off the board before they crash into it
+const EDGE: i64 = 4;
+
+// Islands near the edge with more cells than this are the soup itself
+// spreading out rather than a spaceship, and aren't worth playing out
+const MAX_ESCAPING: usize = 32;
+
+/// A soup search in the style of apgsearch: every seed fills a square
+/// of cells at random, which is played until it settles and then
+/// censused.
+///
+/// Each soup plays in a bounded [`World`] with an empty margin on every
+/// side. Spaceships that reach the edge are counted and taken off the
+/// board, so they don't crash into it, and a soup has settled once the
+/// [`CycleDetector`] sees it repeat. Soups that spread to the edge
+/// themselves would be changed by it, so they are set aside as
+/// unsettled with those that play for too long. Soups are spread over
+/// worker threads, but the results only depend on the seeds.
+///
+/// An unbounded search plays soups on the plane of a [`SparseWorld`]
+/// instead, which has no edge for them to reach. Spaceships are taken
+/// off once they are well ahead of everything else, as
+/// [`Lifespan`](crate::Lifespan) does, so only soups that play for too
+/// long are unsettled. It is also much faster, as only living cells
+/// cost anything. Soups that would have reached the edge of a [`World`]
+/// are counted there, so the two don't find quite the same objects.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Search {
+  /// Cells across and down each soup, `16` by default.
+  pub soup_size: i64,
+  /// Chance of each soup cell starting alive, `0.5` by default.
+  pub density: f64,
+  /// Empty cells around the soup on every side, `128` by default. Only
+  /// a bounded world has an edge beyond them.
+  pub margin: i64,
+  /// Whether soups play in a [`SparseWorld`] rather than a [`World`],
+  /// `false` by default.
+  pub unbounded: bool,
+  /// Birth and survival rule, Conway's `B3/S23` by default.
+  pub rule: Rule,
+  /// Generations a soup may play before it is given up on as
+  /// unsettled, `10000` by default.
+  pub max_generations: u64,
+  /// Soups played at the same time, `1` by default.
+  pub threads: usize,
+  /// Seeds kept for each kind of object, `10` by default.
+  pub samples: usize,
+}
+
+impl Default for Search {
+  fn default() -> Search {
+    Search {
+      soup_size: 16,
+      density: 0.5,
+      margin: 128,
+      unbounded: false,
+      rule: Rule::default(),
+      max_generations: 10_000,
+      threads: 1,
+      samples: 10,
+    }
+  }
+}
+
+/// The objects a [`Search`] has found so far. Results can be saved to
+/// a checkpoint and read back, to carry on with the seeds after them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchResults {
+  /// The seed the search started from.
+  pub first_seed: u64,
+  /// The next seed to search, every seed before it has been.
+  pub next_seed: u64,
+  /// How many of each object were found, by apgcode.
+  pub counts: BTreeMap<String, u64>,
+  /// The first seeds that produced each object, by apgcode.
+  pub samples: BTreeMap<String, Vec<u64>>,
+  /// Seeds of the soups that hadn't settled by the last generation, or
+  /// that spread to the edge of a bounded world.
+  pub unsettled: Vec<u64>,
+}
+
+// What the edge of a soup's world held after a generation. On an
+// unbounded world that is anywhere far enough ahead of everything else
+enum Edge {
+  // Nothing at all
+  Clear,
+  // Only spaceships, which were counted and removed
+  Escaped,
+  // The soup itself, which is too big for the margin to play fairly
+  Reached,
+}
+
+// What one soup left behind
+struct Soup {
+  seed: u64,
+  // Apgcodes of every object, escaped spaceships included, or None
+  // when the soup didn't settle inside its world
+  objects: Option<Vec<String>>,
+}
+
+impl Search {
+  /// Builds the world a seed's soup plays in. The soup is the one
+  /// `World::with_settings` fills a world of the soup's size with, for
+  /// the same seed and density, placed in the middle of the margin.
+  pub fn soup(&self, seed: u64) -> Result<Box<dyn Universe>, WorldError> {
+    let (size, margin) = (self.soup_size.max(1), self.margin.max(0));
+    let settings = Settings {
+      rule: self.rule,
+      seed: Some(seed),
+      density: 0.0,
+      ..Settings::default()
+    };
+    let side = self.side();
+    let mut world: Box<dyn Universe> = if self.unbounded {
+      Box::new(SparseWorld::with_settings(side, side, settings)?)
+    } else {
+      Box::new(World::with_settings(side, side, settings)?)
+    };
+
+    let mut random = Random::new(seed);
+    for y in 0..size {
+      for x in 0..size {
+        if random.next_alive(self.density) {
+          world.set_alive(margin + x, margin + y, true)?;
+        }
+      }
+    }
+    Ok(world)
+  }
+
+  /// Plays the next `soups` seeds after those the results cover, and
+  /// adds what they left behind to them. Nothing is added when the
+  /// soups can't be built, such as for rules a [`SparseWorld`] can't
+  /// play.
+  pub fn run(&self, results: &mut SearchResults, soups: u64) -> Result<(), WorldError> {
+    let first = results.next_seed;
+    let played = workers::map_rows(soups as usize, self.threads, |i| {
+      vec![self.play(first + i as u64)]
+    });
+
+    for soup in played
+      .into_iter()
+      .collect::<Result<Vec<Soup>, WorldError>>()?
+    {
+      results.add(soup, self.samples);
+    }
+    results.next_seed = first + soups;
+    Ok(())
+  }
+
+  // Right-most column and bottom row of the soup and its margin
+  fn side(&self) -> i64 {
+    self.soup_size.max(1) + self.margin.max(0) * 2 - 1
+  }
+
+  fn play(&self, seed: u64) -> Result<Soup, WorldError> {
+    let mut world = self.soup(seed)?;
+    let mut detector = CycleDetector::new();
+    let mut escaped = Vec::new();
+    let mut escapes = Escapes::default();
+
+    while detector.observe(world.as_ref()).is_none() {
+      if world.tick() >= self.max_generations {
+        return Ok(Soup {
+          seed,
+          objects: None,
+        });
+      }
+      world._tick();
+      let edge = if self.unbounded {
+        self.remove_ahead(world.as_mut(), &mut escaped, &mut escapes)?
+      } else {
+        self.remove_escaped(world.as_mut(), &mut escaped)?
+      };
+      match edge {
+        Edge::Clear => {}
+        // Removing cells changes the world's history, so the detector
+        // starts again from here
+        Edge::Escaped => detector = CycleDetector::new(),
+        Edge::Reached => {
+          return Ok(Soup {
+            seed,
+            objects: None,
+          })
+        }
+      }
+    }
+
+    let census = Census::new(world.as_ref(), Connectivity::Moore);
+    let objects = census
+      .islands
+      .into_iter()
+      .map(|island| island.code)
+      .chain(escaped)
+      .collect();
+    Ok(Soup {
+      seed,
+      objects: Some(objects),
+    })
+  }
+
+  // Spaceships are taken off a bounded world as they reach its edge
+  fn remove_escaped(
+    &self,
+    world: &mut dyn Universe,
+    escaped: &mut Vec<String>,
+  ) -> Result<Edge, WorldError> {
+    let side = self.side();
+    let near_edge =
+      |&(x, y): &(i64, i64)| x < EDGE || y < EDGE || x > side - EDGE || y > side - EDGE;
+    let cells = world.live_cells();
+    if !cells.iter().any(near_edge) {
+      return Ok(Edge::Clear);
+    }
+
+    let mut removed = Vec::new();
+    for cells in islands(&cells, Connectivity::Moore) {
+      if !cells.iter().any(near_edge) {
+        continue;
+      }
+      if cells.len() > MAX_ESCAPING {
+        return Ok(Edge::Reached);
+      }
+      let island = Island::new(cells, self.rule);
+      if island.velocity.is_none() {
+        return Ok(Edge::Reached);
+      }
+      removed.push(island);
+    }
+
+    for island in removed {
+      for &(x, y) in &island.cells {
+        world.set_alive(x, y, false)?;
+      }
+      escaped.push(island.code);
+    }
+    Ok(Edge::Escaped)
+  }
+
+  // An unbounded world has no edge to reach, so spaceships are taken
+  // off once nothing else can catch up with them
+  fn remove_ahead(
+    &self,
+    world: &mut dyn Universe,
+    escaped: &mut Vec<String>,
+    escapes: &mut Escapes,
+  ) -> Result<Edge, WorldError> {
+    let removed = escapes.remove(world, self.rule)?;
+    if removed.is_empty() {
+      return Ok(Edge::Clear);
+    }
+    escaped.extend(removed.into_iter().map(|island| island.code));
+    Ok(Edge::Escaped)
+  }
+
+  // Checkpoints only fit a search with the same settings, which the
+  // first line records. Threads and samples don't change what is found
+  fn header(&self) -> String {
+    format!(
+      "#Search rule={} size={} density={} margin={} unbounded={} generations={}",
+      self.rule, self.soup_size, self.density, self.margin, self.unbounded, self.max_generations
+    )
+  }
+}
+
+impl SearchResults {
+  /// Creates empty results for a search starting from a seed.
+  pub fn new(first_seed: u64) -> SearchResults {
+    SearchResults {
+      first_seed,
+      next_seed: first_seed,
+      ..SearchResults::default()
+    }
+  }
+
+  /// Returns how many soups have been searched.
+  pub fn soups(&self) -> u64 {
+    self.next_seed - self.first_seed
+  }
+
+  /// Returns every kind of object found with its count, most common
+  /// first and by apgcode after that.
+  pub fn common(&self) -> Vec<(&str, u64)> {
+    let mut counts: Vec<(&str, u64)> = self
+      .counts
+      .iter()
+      .map(|(code, &count)| (code.as_str(), count))
+      .collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    counts
+  }
+
+  /// Returns the objects found fewer than `below` times, rarest first,
+  /// with the seeds that produced them.
+  pub fn rare(&self, below: u64) -> Vec<(&str, u64, &[u64])> {
+    let mut rare: Vec<(&str, u64, &[u64])> = self
+      .counts
+      .iter()
+      .filter(|&(_, &count)| count < below)
+      .map(|(code, &count)| (code.as_str(), count, self.samples_of(code)))
+      .collect();
+    rare.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(b.0)));
+    rare
+  }
+
+  /// Writes the results as a checkpoint for the search that found them:
+  ///
+  /// ```text
+  /// #Search rule=B3/S23 size=16 density=0.5 margin=128 unbounded=false generations=10000
+  /// seeds 0 1000
+  /// unsettled 417
+  /// xp2_7 2403 0 1 2 3 5 6 7 8 9 10
+  /// xs4_33 3015 0 1 2 3 4 5 6 7 9 10
+  /// ```
+  ///
+  /// `seeds` gives the first seed and the next one to search, and every
+  /// object has its apgcode, its count and the seeds sampled for it.
+  pub fn to_checkpoint(&self, search: &Search) -> String {
+    let mut checkpoint = search.header();
+    let _ = write!(
+      checkpoint,
+      "\nseeds {} {}\nunsettled",
+      self.first_seed, self.next_seed
+    );
+    for seed in &self.unsettled {
+      let _ = write!(checkpoint, " {}", seed);
+    }
+    for (code, count) in &self.counts {
+      let _ = write!(checkpoint, "\n{} {}", code, count);
+      for seed in self.samples_of(code) {
+        let _ = write!(checkpoint, " {}", seed);
+      }
+    }
+    checkpoint.push('\n');
+    checkpoint
+  }
+
+  /// Reads a checkpoint written by [`SearchResults::to_checkpoint`],
+  /// failing when it was written by a search with other settings.
+  pub fn from_checkpoint(text: &str, search: &Search) -> Result<SearchResults, WorldError> {
+    let invalid = |line: usize, reason: &str| WorldError::InvalidCheckpoint {
+      line,
+      reason: reason.to_string(),
+    };
+    let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+    match lines.next() {
+      Some((_, header)) if header.trim() == search.header() => {}
+      Some((line, header)) if header.starts_with("#Search") => {
+        return Err(invalid(line, "written by a search with other settings"))
+      }
+      _ => return Err(invalid(1, "not a search checkpoint")),
+    }
+
+    let mut results = SearchResults::default();
+    let (line, seeds) = lines.next().unwrap_or((2, ""));
+    match numbers(seeds.strip_prefix("seeds")).as_deref() {
+      Some(&[first, next]) if first <= next => {
+        results.first_seed = first;
+        results.next_seed = next;
+      }
+      _ => return Err(invalid(line, "expected the seeds searched")),
+    }
+    let (line, unsettled) = lines.next().unwrap_or((3, ""));
+    results.unsettled = numbers(unsettled.strip_prefix("unsettled"))
+      .ok_or_else(|| invalid(line, "expected the unsettled seeds"))?;
+
+    for (line, object) in lines.filter(|(_, line)| !line.trim().is_empty()) {
+      let code = object.split(' ').next().unwrap_or_default();
+      match numbers(object.strip_prefix(code)).as_deref() {
+        Some([count, samples @ ..]) => {
+          results.counts.insert(code.to_string(), *count);
+          results.samples.insert(code.to_string(), samples.to_vec());
+        }
+        _ => return Err(invalid(line, "expected an apgcode, its count and seeds")),
+      }
+    }
+    Ok(results)
+  }
+
+  // Both maps are public and can be edited apart, so an object may have
+  // been counted without any seeds being kept for it
+  fn samples_of(&self, code: &str) -> &[u64] {
+    self.samples.get(code).map_or(&[], Vec::as_slice)
+  }
+
+  fn add(&mut self, soup: Soup, samples: usize) {
+    let Some(objects) = soup.objects else {
+      self.unsettled.push(soup.seed);
+      return;
+    };
+
+    for code in objects {
+      let seeds = self.samples.entry(code.clone()).or_default();
+      // A soup with several of an object is sampled once
+      if seeds.len() < samples && seeds.last() != Some(&soup.seed) {
+        seeds.push(soup.seed);
+      }
+      *self.counts.entry(code).or_default() += 1;
+    }
+  }
+}
+
+// Parses the numbers after a line's keyword, which has to be followed
+// by a space or nothing at all
+fn numbers(rest: Option<&str>) -> Option<Vec<u64>> {
+  let rest = rest?;
+  if !rest.is_empty() && !rest.starts_with(' ') {
+    return None;
+  }
+  rest
+    .split_whitespace()
+    .map(|number| number.parse().ok())
+    .collect()
+}
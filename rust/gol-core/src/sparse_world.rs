@@ -0,0 +1,265 @@
+use crate::random::Random;
+use crate::rule::Rule;
+use crate::universe::{BoundingBox, Universe};
+use crate::world::{Settings, World, WorldError, CACHED_DIRECTIONS};
+use std::collections::{HashMap, HashSet};
+
+/// A world on an unbounded plane that only stores its living cells,
+/// plus the living neighbour counts of the frontier around them.
+///
+/// Patterns grow in any direction for as long as they like. The
+/// viewport, `(x, y)` to `(x + width, y + height)`, is the region that
+/// `render` draws.
+pub struct SparseWorld {
+  viewport_x: i64,
+  viewport_y: i64,
+  width: i64,
+  height: i64,
+  tick: u64,
+  rule: Rule,
+
+  cells: HashSet<(i64, i64)>,
+  neighbours: HashMap<(i64, i64), u8>,
+}
+
+impl SparseWorld {
+  /// Creates a Conway world with a random soup of cells in a viewport
+  /// at the origin.
+  pub fn new(width: i64, height: i64) -> SparseWorld {
+    SparseWorld::with_settings(width, height, Settings::default())
+      .expect("the default settings are supported")
+  }
+
+  /// Creates a world with the given settings and a random soup of cells
+  /// in a viewport at the origin, the same soup [`World`] gets from
+  /// those settings. The plane has no edges, so only
+  /// [`Topology::Bounded`](crate::Topology::Bounded) is accepted, and
+  /// rules where cells are born without neighbours (`B0`) would fill the
+  /// whole plane. Each generation is played on the calling thread.
+  pub fn with_settings(
+    width: i64,
+    height: i64,
+    settings: Settings,
+  ) -> Result<SparseWorld, WorldError> {
+    settings.check_unbounded()?;
+
+    let mut world = SparseWorld::empty(width, height, settings.rule);
+    world.populate_cells(settings.soup_seed(), settings.density);
+    Ok(world)
+  }
+
+  fn empty(width: i64, height: i64, rule: Rule) -> SparseWorld {
+    SparseWorld {
+      viewport_x: 0,
+      viewport_y: 0,
+      width,
+      height,
+      tick: 0,
+      rule,
+      cells: HashSet::new(),
+      neighbours: HashMap::new(),
+    }
+  }
+
+  fn populate_cells(&mut self, seed: u64, density: f64) {
+    let mut random = Random::new(seed);
+
+    for y in 0..=self.height {
+      for x in 0..=self.width {
+        let alive = random.next_alive(density);
+        self.set_alive(x, y, alive);
+      }
+    }
+  }
+
+  /// Returns the birth and survival rule of the world.
+  pub fn rule(&self) -> Rule {
+    self.rule
+  }
+
+  /// Returns the number of living cells.
+  pub fn population(&self) -> u64 {
+    self.cells.len() as u64
+  }
+
+  /// Returns the locations of the living cells, in no particular order.
+  pub fn live_cells(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+    self.cells.iter().copied()
+  }
+
+  /// Returns the box around every living cell, or `None` when the
+  /// world is empty.
+  pub fn bounding_box(&self) -> Option<BoundingBox> {
+    let mut cells = self.cells.iter();
+    let &(x, y) = cells.next()?;
+    let mut bounding_box = BoundingBox {
+      min_x: x,
+      min_y: y,
+      max_x: x,
+      max_y: y,
+    };
+    for &(x, y) in cells {
+      bounding_box.include(x, y);
+    }
+    Some(bounding_box)
+  }
+
+  /// Returns the top left corner of the viewport.
+  pub fn viewport(&self) -> (i64, i64) {
+    (self.viewport_x, self.viewport_y)
+  }
+
+  /// Moves and resizes the viewport that `render` draws.
+  pub fn set_viewport(&mut self, x: i64, y: i64, width: i64, height: i64) {
+    self.viewport_x = x;
+    self.viewport_y = y;
+    self.width = width;
+    self.height = height;
+  }
+
+  /// Renders the cells from `(x, y)` to `(x + width, y + height)`, `o`
+  /// for alive and space for dead, with one line per row.
+  pub fn render_viewport(&self, x: i64, y: i64, width: i64, height: i64) -> String {
+    let mut rendering = String::with_capacity(((width + 2) * (height + 1)) as usize);
+    for row in y..=y + height {
+      for column in x..=x + width {
+        rendering.push(if self.cells.contains(&(column, row)) {
+          'o'
+        } else {
+          ' '
+        });
+      }
+      rendering.push('\n');
+    }
+    rendering
+  }
+
+  /// Sets the cell at a location.
+  pub fn set_alive(&mut self, x: i64, y: i64, alive: bool) {
+    let changed = if alive {
+      self.cells.insert((x, y))
+    } else {
+      self.cells.remove(&(x, y))
+    };
+
+    if changed {
+      self.count_neighbours(x, y, alive);
+    }
+  }
+
+  // Keeps the frontier's neighbour counts in step with a cell that
+  // was just born or died
+  fn count_neighbours(&mut self, x: i64, y: i64, alive: bool) {
+    for [rel_x, rel_y] in CACHED_DIRECTIONS {
+      let location = (x + rel_x, y + rel_y);
+      if alive {
+        *self.neighbours.entry(location).or_insert(0) += 1;
+      } else if let Some(count) = self.neighbours.get_mut(&location) {
+        *count -= 1;
+        if *count == 0 {
+          self.neighbours.remove(&location);
+        }
+      }
+    }
+  }
+}
+
+impl Universe for SparseWorld {
+  fn width(&self) -> i64 {
+    self.width
+  }
+
+  fn height(&self) -> i64 {
+    self.height
+  }
+
+  fn tick(&self) -> u64 {
+    self.tick
+  }
+
+  fn set_tick(&mut self, tick: u64) {
+    self.tick = tick;
+  }
+
+  fn rule(&self) -> Rule {
+    self.rule
+  }
+
+  fn _tick(&mut self) {
+    // First determine the action for the living cells and the frontier
+    let deaths: Vec<(i64, i64)> = self
+      .cells
+      .iter()
+      .copied()
+      .filter(|location| {
+        let alive_neighbours = self.neighbours.get(location).copied().unwrap_or(0);
+        !self.rule.survives(alive_neighbours as usize)
+      })
+      .collect();
+    let births: Vec<(i64, i64)> = self
+      .neighbours
+      .iter()
+      .filter(|&(location, &alive_neighbours)| {
+        !self.cells.contains(location) && self.rule.born(alive_neighbours as usize)
+      })
+      .map(|(&location, _)| location)
+      .collect();
+
+    // Then execute the determined action for those cells
+    for (x, y) in deaths {
+      self.set_alive(x, y, false);
+    }
+    for (x, y) in births {
+      self.set_alive(x, y, true);
+    }
+
+    self.tick += 1;
+  }
+
+  fn alive_at(&self, x: i64, y: i64) -> bool {
+    self.cells.contains(&(x, y))
+  }
+
+  fn set_alive(&mut self, x: i64, y: i64, alive: bool) -> Result<(), WorldError> {
+    SparseWorld::set_alive(self, x, y, alive);
+    Ok(())
+  }
+
+  fn in_bounds(&self, _x: i64, _y: i64) -> bool {
+    true
+  }
+
+  fn live_cells(&self) -> Vec<(i64, i64)> {
+    SparseWorld::live_cells(self).collect()
+  }
+
+  fn viewport(&self) -> (i64, i64) {
+    SparseWorld::viewport(self)
+  }
+
+  fn render(&self) -> String {
+    self.render_viewport(self.viewport_x, self.viewport_y, self.width, self.height)
+  }
+}
+
+impl TryFrom<&World> for SparseWorld {
+  type Error = WorldError;
+
+  fn try_from(world: &World) -> Result<SparseWorld, WorldError> {
+    let rule = world.rule();
+    if rule.born(0) {
+      return Err(WorldError::UnsupportedRule { rule });
+    }
+
+    let mut sparse_world = SparseWorld::empty(world.width(), world.height(), rule);
+    sparse_world.tick = world.tick();
+    for y in 0..=world.height() {
+      for x in 0..=world.width() {
+        if world.cell_at(x, y).is_some_and(|cell| cell.alive) {
+          sparse_world.set_alive(x, y, true);
+        }
+      }
+    }
+    Ok(sparse_world)
+  }
+}
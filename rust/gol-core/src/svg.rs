@@ -0,0 +1,151 @@
+use crate::render::{self, Renderer};
+use crate::universe::{BoundingBox, Universe};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Draws universes as SVG, one square per living cell, for figures that
+/// have to stay sharp at any size.
+///
+/// Neighbouring living cells in a row are merged into one rectangle
+/// unless `merge_runs` is off, which keeps files of dense worlds small.
+/// Cells listed in `cell_styles` are only merged with cells of the same
+/// style:
+///
+/// ```text
+/// <svg xmlns="http://www.w3.org/2000/svg" width="30" height="20" ...>
+/// <rect width="30" height="20" fill="#fff"/>
+/// <g fill="#000">
+/// <rect x="0" y="0" width="20" height="10"/>
+/// <rect x="20" y="10" width="10" height="10" style="fill:red"/>
+/// </g>
+/// </svg>
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SvgRenderer {
+  /// Cells to draw, or `None` for the region `render` covers.
+  pub viewport: Option<BoundingBox>,
+  /// Width and height of each cell in SVG units, `10` by default.
+  pub scale: u32,
+  /// Whether runs of living cells are drawn as one rectangle, `true` by
+  /// default.
+  pub merge_runs: bool,
+  /// Fill of living cells, `#000` by default.
+  pub fill: String,
+  /// Fill behind the cells, `#fff` by default, or `None` to leave the
+  /// background transparent.
+  pub background: Option<String>,
+  /// A `style` attribute for particular living cells, such as
+  /// `fill:red`, keyed by location. Dead cells are never drawn.
+  pub cell_styles: HashMap<(i64, i64), String>,
+}
+
+impl Default for SvgRenderer {
+  fn default() -> SvgRenderer {
+    SvgRenderer {
+      viewport: None,
+      scale: 10,
+      merge_runs: true,
+      fill: "#000".to_string(),
+      background: Some("#fff".to_string()),
+      cell_styles: HashMap::new(),
+    }
+  }
+}
+
+impl Renderer for SvgRenderer {
+  // The region given here wins over the viewport option
+  fn render_region(&self, universe: &dyn Universe, viewport: BoundingBox) -> String {
+    let scale = self.scale as u64;
+    let (width, height) = (viewport.width() * scale, viewport.height() * scale);
+
+    // Writing to a String can't fail, so the results are ignored
+    let mut svg = String::new();
+    let _ = writeln!(
+      svg,
+      "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+       viewBox=\"0 0 {} {}\" shape-rendering=\"crispEdges\">",
+      width, height, width, height
+    );
+    if let Some(background) = &self.background {
+      let _ = writeln!(
+        svg,
+        "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+        width,
+        height,
+        escape(background)
+      );
+    }
+    let _ = writeln!(svg, "<g fill=\"{}\">", escape(&self.fill));
+
+    for y in viewport.min_y..=viewport.max_y {
+      // A run is the column it starts at and the style it shares
+      let mut run: Option<(i64, Option<&String>)> = None;
+      for x in viewport.min_x..=viewport.max_x {
+        let cell = universe
+          .alive_at(x, y)
+          .then(|| self.cell_styles.get(&(x, y)));
+        match (run, cell) {
+          (Some((_, style)), Some(cell_style)) if self.merge_runs && style == cell_style => {}
+          _ => {
+            if let Some((start, style)) = run {
+              self.write_rect(&mut svg, &viewport, (start, x - 1, y), style);
+            }
+            run = cell.map(|style| (x, style));
+          }
+        }
+      }
+      if let Some((start, style)) = run {
+        self.write_rect(&mut svg, &viewport, (start, viewport.max_x, y), style);
+      }
+    }
+
+    svg.push_str("</g>\n</svg>\n");
+    svg
+  }
+
+  fn render(&self, universe: &dyn Universe) -> String {
+    let viewport = self.viewport.unwrap_or_else(|| render::viewport(universe));
+    self.render_region(universe, viewport)
+  }
+}
+
+impl SvgRenderer {
+  // Draws the cells from `start` to `end` on row `y`
+  fn write_rect(
+    &self,
+    svg: &mut String,
+    viewport: &BoundingBox,
+    (start, end, y): (i64, i64, i64),
+    style: Option<&String>,
+  ) {
+    let scale = self.scale as u64;
+    let _ = write!(
+      svg,
+      "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"",
+      start.abs_diff(viewport.min_x) * scale,
+      y.abs_diff(viewport.min_y) * scale,
+      (end.abs_diff(start) + 1) * scale,
+      scale
+    );
+    if let Some(style) = style {
+      let _ = write!(svg, " style=\"{}\"", escape(style));
+    }
+    svg.push_str("/>\n");
+  }
+}
+
+// Attribute values are quoted with ", so that and the markup characters
+// have to be escaped
+fn escape(value: &str) -> String {
+  let mut escaped = String::with_capacity(value.len());
+  for char in value.chars() {
+    match char {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      char => escaped.push(char),
+    }
+  }
+  escaped
+}
@@ -0,0 +1,161 @@
+use crate::universe::BoundingBox;
+use std::fmt::Write;
+
+// Bars of a sparkline from lowest to highest
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// What happened in one generation of a [`World`](crate::World).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenerationStats {
+  /// The generation these are for.
+  pub generation: u64,
+  /// Living cells at the end of the generation.
+  pub population: u64,
+  /// Cells that came to life this generation.
+  pub births: u64,
+  /// Cells that died this generation.
+  pub deaths: u64,
+  /// Box around the living cells, or `None` when nothing is alive.
+  pub bounding_box: Option<BoundingBox>,
+  /// Cells whose `next_state` was decided differently than the
+  /// generation before, a measure of how much of the world is active.
+  pub changed: u64,
+}
+
+/// Statistics for every generation a world played while it was being
+/// tracked, oldest first, for plotting how a soup evolves.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timeline {
+  /// One entry per generation.
+  pub generations: Vec<GenerationStats>,
+}
+
+impl Timeline {
+  /// Writes the timeline as CSV with a header row. The bounding box is
+  /// four columns, left empty when nothing is alive:
+  ///
+  /// ```text
+  /// generation,population,births,deaths,min_x,min_y,max_x,max_y,changed
+  /// 0,1266,0,0,0,0,150,40,0
+  /// 1,1012,205,459,0,0,150,40,664
+  /// ```
+  pub fn to_csv(&self) -> String {
+    let mut csv =
+      "generation,population,births,deaths,min_x,min_y,max_x,max_y,changed\n".to_string();
+    for stats in &self.generations {
+      let bounding_box = stats
+        .bounding_box
+        .map_or(",,,".to_string(), |bounding_box| {
+          format!(
+            "{},{},{},{}",
+            bounding_box.min_x, bounding_box.min_y, bounding_box.max_x, bounding_box.max_y
+          )
+        });
+      // Writing to a String can't fail
+      let _ = writeln!(
+        csv,
+        "{},{},{},{},{},{}",
+        stats.generation, stats.population, stats.births, stats.deaths, bounding_box, stats.changed
+      );
+    }
+    csv
+  }
+
+  /// Writes the timeline as a JSON array with one object per
+  /// generation, with `null` for the bounding box when nothing is
+  /// alive.
+  pub fn to_json(&self) -> String {
+    let mut json = "[".to_string();
+    for (i, stats) in self.generations.iter().enumerate() {
+      let bounding_box = stats
+        .bounding_box
+        .map_or("null".to_string(), |bounding_box| {
+          format!(
+            "{{\"min_x\": {}, \"min_y\": {}, \"max_x\": {}, \"max_y\": {}}}",
+            bounding_box.min_x, bounding_box.min_y, bounding_box.max_x, bounding_box.max_y
+          )
+        });
+      let _ = write!(
+        json,
+        "{}\n  {{\"generation\": {}, \"population\": {}, \"births\": {}, \"deaths\": {}, \
+         \"bounding_box\": {}, \"changed\": {}}}",
+        if i == 0 { "" } else { "," },
+        stats.generation,
+        stats.population,
+        stats.births,
+        stats.deaths,
+        bounding_box,
+        stats.changed
+      );
+    }
+    json.push_str("\n]\n");
+    json
+  }
+
+  /// Draws the population of the last `width` generations as a line of
+  /// bars, scaled from the lowest to the highest of them.
+  pub fn sparkline(&self, width: usize) -> String {
+    let start = self.generations.len().saturating_sub(width);
+    let populations: Vec<u64> = self.generations[start..]
+      .iter()
+      .map(|stats| stats.population)
+      .collect();
+    let low = populations.iter().copied().min().unwrap_or(0);
+    let high = populations.iter().copied().max().unwrap_or(0);
+
+    populations
+      .iter()
+      .map(|&population| {
+        let bar = match high - low {
+          0 => 0,
+          range => ((population - low) * (BARS.len() as u64 - 1) + range / 2) / range,
+        };
+        BARS[bar as usize]
+      })
+      .collect()
+  }
+}
+
+// Adds up the statistics of a generation one cell at a time, while the
+// world applies its decisions
+#[derive(Debug, Default)]
+pub(crate) struct Tally {
+  population: u64,
+  births: u64,
+  deaths: u64,
+  bounding_box: Option<BoundingBox>,
+  changed: u64,
+}
+
+impl Tally {
+  pub(crate) fn count(&mut self, (x, y): (i64, i64), was_alive: bool, alive: bool, changed: bool) {
+    if alive {
+      self.population += 1;
+      match &mut self.bounding_box {
+        Some(bounding_box) => bounding_box.include(x, y),
+        None => {
+          self.bounding_box = Some(BoundingBox {
+            min_x: x,
+            min_y: y,
+            max_x: x,
+            max_y: y,
+          })
+        }
+      }
+    }
+    self.births += (!was_alive && alive) as u64;
+    self.deaths += (was_alive && !alive) as u64;
+    self.changed += changed as u64;
+  }
+
+  pub(crate) fn finish(self, generation: u64) -> GenerationStats {
+    GenerationStats {
+      generation,
+      population: self.population,
+      births: self.births,
+      deaths: self.deaths,
+      bounding_box: self.bounding_box,
+      changed: self.changed,
+    }
+  }
+}
@@ -0,0 +1,51 @@
+/// How the edges of a finite world are joined together.
+///
+/// Neighbours that fall off one edge either vanish (`Bounded`) or come
+/// back in on the opposite edge, mirrored along that edge for the
+/// twisted surfaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Topology {
+  /// A plane with dead cells beyond every edge.
+  #[default]
+  Bounded,
+  /// Left joins right and top joins bottom.
+  Torus,
+  /// Left joins right, top joins bottom with the columns mirrored.
+  KleinBottle,
+  /// Both pairs of edges are joined with a mirror.
+  CrossSurface,
+}
+
+impl Topology {
+  /// Maps a location next to a `columns` by `rows` grid back onto the
+  /// grid, or returns `None` when it lies beyond a dead edge.
+  pub fn wrap(&self, x: i64, y: i64, columns: i64, rows: i64) -> Option<(i64, i64)> {
+    let outside_x = x < 0 || x >= columns;
+    let outside_y = y < 0 || y >= rows;
+
+    match self {
+      Topology::Bounded if outside_x || outside_y => None,
+      Topology::Bounded | Topology::Torus => Some((x.rem_euclid(columns), y.rem_euclid(rows))),
+      Topology::KleinBottle => {
+        let x = x.rem_euclid(columns);
+        if outside_y {
+          Some((columns - 1 - x, y.rem_euclid(rows)))
+        } else {
+          Some((x, y))
+        }
+      }
+      Topology::CrossSurface => {
+        let (mut x, mut y) = (x, y);
+        if outside_x {
+          x = x.rem_euclid(columns);
+          y = rows - 1 - y;
+        }
+        if y < 0 || y >= rows {
+          y = y.rem_euclid(rows);
+          x = columns - 1 - x;
+        }
+        Some((x, y))
+      }
+    }
+  }
+}
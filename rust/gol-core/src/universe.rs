@@ -0,0 +1,104 @@
+use crate::rule::Rule;
+use crate::timeline::Timeline;
+use crate::world::WorldError;
+
+/// The operations every world backend offers, so drivers and tools can
+/// swap how cells are stored without changing how they are played.
+pub trait Universe {
+  /// Returns the right-most column of the world.
+  fn width(&self) -> i64;
+
+  /// Returns the bottom row of the world.
+  fn height(&self) -> i64;
+
+  /// Returns how many generations have been played.
+  fn tick(&self) -> u64;
+
+  /// Sets how many generations have been played, for worlds restored
+  /// from a saved frame or pattern.
+  fn set_tick(&mut self, tick: u64);
+
+  /// Returns the birth and survival rule being played.
+  fn rule(&self) -> Rule;
+
+  /// Advances the world by one generation.
+  fn _tick(&mut self);
+
+  /// Returns whether the cell at a location is alive.
+  fn alive_at(&self, x: i64, y: i64) -> bool;
+
+  /// Brings the cell at a location to life or kills it. Bounded
+  /// backends reject locations outside the world.
+  fn set_alive(&mut self, x: i64, y: i64, alive: bool) -> Result<(), WorldError>;
+
+  /// Returns whether a location can hold a cell. Bounded backends only
+  /// hold `(0, 0)` to `(width, height)`, unbounded ones hold anything.
+  fn in_bounds(&self, x: i64, y: i64) -> bool {
+    x >= 0 && y >= 0 && x <= self.width() && y <= self.height()
+  }
+
+  /// Returns the locations of every living cell, in no particular
+  /// order. Unbounded backends include cells outside the viewport.
+  fn live_cells(&self) -> Vec<(i64, i64)> {
+    let mut cells = Vec::new();
+    for y in 0..=self.height() {
+      for x in 0..=self.width() {
+        if self.alive_at(x, y) {
+          cells.push((x, y));
+        }
+      }
+    }
+    cells
+  }
+
+  /// Returns the top left corner of the region `render` draws, which
+  /// is `(0, 0)` unless the backend has a movable viewport.
+  fn viewport(&self) -> (i64, i64) {
+    (0, 0)
+  }
+
+  /// Returns the statistics kept for every generation played, if the
+  /// backend keeps them. Only [`World`](crate::World) does, once asked
+  /// to with `track_timeline`.
+  fn timeline(&self) -> Option<&Timeline> {
+    None
+  }
+
+  /// Renders the world as text, `o` for alive and space for dead, with
+  /// one line per row.
+  fn render(&self) -> String;
+}
+
+/// The smallest rectangle holding every living cell, inclusive on all
+/// sides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BoundingBox {
+  /// Left-most column with a living cell.
+  pub min_x: i64,
+  /// Top row with a living cell.
+  pub min_y: i64,
+  /// Right-most column with a living cell.
+  pub max_x: i64,
+  /// Bottom row with a living cell.
+  pub max_y: i64,
+}
+
+impl BoundingBox {
+  /// Returns the number of columns the box spans.
+  pub fn width(&self) -> u64 {
+    self.max_x.abs_diff(self.min_x) + 1
+  }
+
+  /// Returns the number of rows the box spans.
+  pub fn height(&self) -> u64 {
+    self.max_y.abs_diff(self.min_y) + 1
+  }
+
+  /// Grows the box, if needed, to hold a location.
+  pub fn include(&mut self, x: i64, y: i64) {
+    self.min_x = self.min_x.min(x);
+    self.min_y = self.min_y.min(y);
+    self.max_x = self.max_x.max(x);
+    self.max_y = self.max_y.max(y);
+  }
+}
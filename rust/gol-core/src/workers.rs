@@ -0,0 +1,34 @@
+use std::thread;
+
+// Maps every row to its results on a pool of scoped worker threads,
+// each taking one contiguous band of rows. The bands are joined back
+// in row order, so the results never depend on the number of threads
+pub(crate) fn map_rows<T, F>(rows: usize, threads: usize, work: F) -> Vec<T>
+where
+  T: Send,
+  F: Fn(usize) -> Vec<T> + Sync,
+{
+  if threads <= 1 || rows <= 1 {
+    return (0..rows).flat_map(work).collect();
+  }
+
+  let band = rows.div_ceil(threads);
+  thread::scope(|scope| {
+    let work = &work;
+    let workers: Vec<_> = (0..rows)
+      .step_by(band)
+      .map(|start| {
+        scope.spawn(move || {
+          (start..(start + band).min(rows))
+            .flat_map(work)
+            .collect::<Vec<T>>()
+        })
+      })
+      .collect();
+
+    workers
+      .into_iter()
+      .flat_map(|worker| worker.join().expect("tick worker panicked"))
+      .collect()
+  })
+}
@@ -0,0 +1,740 @@
+use crate::hashlife::HashLife;
+use crate::pattern::Pattern;
+use crate::random::Random;
+use crate::rule::Rule;
+use crate::timeline::{Tally, Timeline};
+use crate::topology::Topology;
+use crate::universe::Universe;
+use crate::workers;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::mem;
+
+// Rust doesn't have exceptions, errors are values returned in a Result
+/// Errors raised while building or editing a [`World`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorldError {
+  /// A cell already exists at this location.
+  LocationOccupied {
+    /// Column of the occupied location.
+    x: i64,
+    /// Row of the occupied location.
+    y: i64,
+  },
+  /// A rulestring couldn't be parsed.
+  InvalidRule {
+    /// The rulestring as it was given.
+    rule: String,
+  },
+  /// The rule can't be played by this backend.
+  UnsupportedRule {
+    /// The rule that was rejected.
+    rule: Rule,
+  },
+  /// A world can't be this size, as it is negative or holds more cells
+  /// than fit in memory.
+  InvalidSize {
+    /// Right-most column asked for.
+    width: i64,
+    /// Bottom row asked for.
+    height: i64,
+  },
+  /// Playing on would take the generation count past `u64::MAX`, or
+  /// cells past the `i64` coordinates of the plane.
+  TooManyGenerations {
+    /// The generation play would have started from.
+    tick: u64,
+  },
+  /// The location lies outside the world.
+  OutOfBounds {
+    /// Column of the location.
+    x: i64,
+    /// Row of the location.
+    y: i64,
+  },
+  /// The topology can't be played by this backend.
+  UnsupportedTopology {
+    /// The topology that was rejected.
+    topology: Topology,
+  },
+  /// A pattern file couldn't be parsed.
+  InvalidPattern {
+    /// Line of the file the problem was found on, counting from 1.
+    line: usize,
+    /// What was wrong with it.
+    reason: String,
+  },
+  /// A pattern file holds a cell outside the size it declares.
+  CellOutsidePattern {
+    /// Line of the file the cell was found on, counting from 1.
+    line: usize,
+    /// Column of the cell.
+    x: i64,
+    /// Row of the cell.
+    y: i64,
+    /// Number of columns the file declares.
+    columns: i64,
+    /// Number of rows the file declares.
+    rows: i64,
+  },
+  /// A pattern file is not in the format it was read as, or is in a
+  /// version of it that isn't supported.
+  UnknownFormat {
+    /// Line of the file holding the header, counting from 1.
+    line: usize,
+    /// The header as it was found.
+    header: String,
+  },
+  /// A universe is too big to flatten into a [`World`].
+  TooLargeToFlatten {
+    /// Number of columns the living cells span.
+    columns: u64,
+    /// Number of rows the living cells span.
+    rows: u64,
+  },
+  /// An image would be wider or taller than the encoders allow.
+  ImageTooLarge {
+    /// Width of the image in pixels.
+    width: u64,
+    /// Height of the image in pixels.
+    height: u64,
+  },
+  /// A pattern placed at this location would stick out of the world.
+  PatternDoesNotFit {
+    /// Column of the pattern's top left corner.
+    x: i64,
+    /// Row of the pattern's top left corner.
+    y: i64,
+    /// Number of columns the pattern spans.
+    columns: i64,
+    /// Number of rows the pattern spans.
+    rows: i64,
+  },
+  /// A search checkpoint couldn't be parsed, or was written by a search
+  /// with other settings.
+  InvalidCheckpoint {
+    /// Line of the file the problem was found on, counting from 1.
+    line: usize,
+    /// What was wrong with it.
+    reason: String,
+  },
+}
+
+impl fmt::Display for WorldError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      WorldError::LocationOccupied { x, y } => write!(f, "LocationOccupied {}-{}", x, y),
+      WorldError::InvalidRule { rule } => write!(f, "InvalidRule {}", rule),
+      WorldError::UnsupportedRule { rule } => write!(f, "UnsupportedRule {}", rule),
+      WorldError::InvalidSize { width, height } => write!(f, "InvalidSize {}x{}", width, height),
+      WorldError::TooManyGenerations { tick } => write!(f, "TooManyGenerations from {}", tick),
+      WorldError::OutOfBounds { x, y } => write!(f, "OutOfBounds {}-{}", x, y),
+      WorldError::UnsupportedTopology { topology } => {
+        write!(f, "UnsupportedTopology {:?}", topology)
+      }
+      WorldError::InvalidPattern { line, reason } => {
+        write!(f, "InvalidPattern line {}: {}", line, reason)
+      }
+      WorldError::CellOutsidePattern {
+        line,
+        x,
+        y,
+        columns,
+        rows,
+      } => write!(
+        f,
+        "CellOutsidePattern line {}: {}-{} outside {}x{}",
+        line, x, y, columns, rows
+      ),
+      WorldError::UnknownFormat { line, header } => {
+        write!(f, "UnknownFormat line {}: {}", line, header)
+      }
+      WorldError::TooLargeToFlatten { columns, rows } => {
+        write!(f, "TooLargeToFlatten {}x{}", columns, rows)
+      }
+      WorldError::ImageTooLarge { width, height } => {
+        write!(f, "ImageTooLarge {}x{}", width, height)
+      }
+      WorldError::PatternDoesNotFit {
+        x,
+        y,
+        columns,
+        rows,
+      } => write!(f, "PatternDoesNotFit {}x{} at {}-{}", columns, rows, x, y),
+      WorldError::InvalidCheckpoint { line, reason } => {
+        write!(f, "InvalidCheckpoint line {}: {}", line, reason)
+      }
+    }
+  }
+}
+
+impl Error for WorldError {}
+
+// Shared with the other backends so they agree on what a neighbour is
+#[rustfmt::skip]
+pub(crate) const CACHED_DIRECTIONS: [[i64; 2]; 8] = [
+  [-1, 1],  [0, 1],  [1, 1],  // above
+  [-1, 0],           [1, 0],  // sides
+  [-1, -1], [0, -1], [1, -1], // below
+];
+
+/// Optional parameters for creating a [`World`].
+#[derive(Debug, Clone)]
+pub struct Settings {
+  /// Birth and survival rule, Conway's `B3/S23` by default.
+  pub rule: Rule,
+  /// How neighbours wrap around the edges, bounded by default.
+  pub topology: Topology,
+  /// Worker threads that play each generation, `1` by default.
+  pub threads: usize,
+  /// Seed of the random soup, taken from the clock when `None`.
+  pub seed: Option<u64>,
+  /// Chance of each cell in the soup starting alive, `0.2` by default.
+  pub density: f64,
+}
+
+impl Default for Settings {
+  fn default() -> Settings {
+    Settings {
+      rule: Rule::default(),
+      topology: Topology::default(),
+      threads: 1,
+      seed: None,
+      density: 0.2,
+    }
+  }
+}
+
+impl Settings {
+  pub(crate) fn soup_seed(&self) -> u64 {
+    self.seed.unwrap_or_else(Random::clock_seed)
+  }
+
+  // Bounded worlds hold every location from (0, 0) to (width, height),
+  // so both have to be positive and all of them, at the bytes each one
+  // takes, have to fit in memory. Returns the number of columns and rows
+  pub(crate) fn check_size(
+    width: i64,
+    height: i64,
+    location_bytes: usize,
+  ) -> Result<(usize, usize), WorldError> {
+    let invalid = || WorldError::InvalidSize { width, height };
+    let count = |last: i64| {
+      last
+        .checked_add(1)
+        .and_then(|count| usize::try_from(count).ok())
+        .ok_or_else(invalid)
+    };
+    let (columns, rows) = (count(width)?, count(height)?);
+    columns
+      .checked_mul(rows)
+      .and_then(|locations| locations.checked_mul(location_bytes))
+      .filter(|&bytes| bytes <= isize::MAX as usize)
+      .ok_or_else(invalid)?;
+    Ok((columns, rows))
+  }
+
+  // Worlds on an unbounded plane have no edges to join, and cells
+  // born without neighbours would fill the whole plane
+  pub(crate) fn check_unbounded(&self) -> Result<(), WorldError> {
+    if self.rule.born(0) {
+      return Err(WorldError::UnsupportedRule { rule: self.rule });
+    }
+    if self.topology != Topology::Bounded {
+      return Err(WorldError::UnsupportedTopology {
+        topology: self.topology,
+      });
+    }
+    Ok(())
+  }
+}
+
+/// A finite Game of Life universe.
+///
+/// Cells live at every location from `(0, 0)` to `(width, height)`
+/// inclusive, keyed by `"x-y"` strings like the other implementations.
+#[derive(Clone)]
+pub struct World {
+  width: i64,
+  height: i64,
+  tick: u64,
+  rule: Rule,
+  topology: Topology,
+  threads: usize,
+
+  cells: HashMap<String, Cell>,
+  cached_directions: [[i64; 2]; 8],
+  timeline: Option<Timeline>,
+}
+
+impl World {
+  /// Most cells a world flattened from a [`HashLife`] may hold, as
+  /// every one of them is a separate entry in the cells map.
+  pub const MAX_FLATTENED_CELLS: u64 = 1 << 20;
+
+  /// Creates a Conway world and fills it with a random soup of cells.
+  pub fn new(width: i64, height: i64) -> Result<World, WorldError> {
+    World::with_settings(width, height, Settings::default())
+  }
+
+  /// Creates a world with the given settings and fills it with a random
+  /// soup of cells. The same seed, density and size always give the
+  /// same soup. Negative sizes, and sizes with more cells than fit in
+  /// memory, are rejected.
+  pub fn with_settings(width: i64, height: i64, settings: Settings) -> Result<World, WorldError> {
+    let mut world = World::empty(width, height, &settings)?;
+    world.populate_cells(settings.soup_seed(), settings.density);
+    world.prepopulate_neighbours();
+    Ok(world)
+  }
+
+  /// Creates a world with the given settings that holds only a pattern,
+  /// with the pattern's top left corner at `(x, y)`. The world plays
+  /// the settings' rule, not the pattern's, has no soup, and starts at
+  /// the pattern's generation.
+  pub fn from_pattern(
+    width: i64,
+    height: i64,
+    settings: Settings,
+    pattern: &Pattern,
+    x: i64,
+    y: i64,
+  ) -> Result<World, WorldError> {
+    pattern.check_fits(x, y, |x, y| x >= 0 && y >= 0 && x <= width && y <= height)?;
+
+    let mut world = World::empty(width, height, &settings)?;
+    for &(rel_x, rel_y) in &pattern.cells {
+      world.add_cell(x + rel_x, y + rel_y, true)?;
+    }
+
+    // Every location the pattern left empty gets a dead cell
+    for y in 0..=height {
+      for x in 0..=width {
+        if world.cell_at(x, y).is_none() {
+          world
+            .add_cell(x, y, false)
+            .expect("the location was just checked");
+        }
+      }
+    }
+
+    world.prepopulate_neighbours();
+    world.tick = pattern.generation;
+    Ok(world)
+  }
+
+  /// Rebuilds a world from a frame printed by `render`, sized to the
+  /// frame and at the generation in the `#N - World tick took...` line
+  /// above it, if there is one. See [`Pattern::from_render`].
+  pub fn from_render(text: &str, settings: Settings) -> Result<World, WorldError> {
+    let pattern = Pattern::from_render(text)?;
+    World::from_pattern(
+      pattern.columns - 1,
+      pattern.rows - 1,
+      settings,
+      &pattern,
+      0,
+      0,
+    )
+  }
+
+  fn empty(width: i64, height: i64, settings: &Settings) -> Result<World, WorldError> {
+    let location_bytes = mem::size_of::<(String, Cell)>();
+    let (columns, rows) = Settings::check_size(width, height, location_bytes)?;
+    Ok(World {
+      width,
+      height,
+      tick: 0,
+      rule: settings.rule,
+      topology: settings.topology,
+      threads: settings.threads.max(1),
+      cells: HashMap::with_capacity(columns * rows),
+      cached_directions: CACHED_DIRECTIONS,
+      timeline: None,
+    })
+  }
+
+  /// Returns the right-most column of the world.
+  pub fn width(&self) -> i64 {
+    self.width
+  }
+
+  /// Returns the bottom row of the world.
+  pub fn height(&self) -> i64 {
+    self.height
+  }
+
+  /// Returns the birth and survival rule of the world.
+  pub fn rule(&self) -> Rule {
+    self.rule
+  }
+
+  /// Returns how the edges of the world are joined together.
+  pub fn topology(&self) -> Topology {
+    self.topology
+  }
+
+  /// Returns how many generations have been played.
+  pub fn tick(&self) -> u64 {
+    self.tick
+  }
+
+  /// Returns how many worker threads play each generation.
+  pub fn threads(&self) -> usize {
+    self.threads
+  }
+
+  /// Sets how many worker threads play each generation, `1` plays on
+  /// the calling thread. The outcome is the same for any number.
+  pub fn set_threads(&mut self, threads: usize) {
+    self.threads = threads.max(1);
+  }
+
+  /// Advances the world by one generation.
+  pub fn _tick(&mut self) {
+    if self.threads > 1 {
+      self.parallel_tick();
+      return;
+    }
+
+    // First determine the action for all cells
+    // The borrow checker won't let us write to a cell while its
+    // neighbours are being read, so collect the states up front
+    let next_states: Vec<Option<u8>> = self
+      .cells
+      .values()
+      .map(|cell| self.next_state(cell))
+      .collect();
+
+    // Then execute the determined action for all cells
+    // (values_mut visits the cells in the same order as values)
+    let mut tally = self.timeline.as_ref().map(|_| Tally::default());
+    for (cell, next_state) in self.cells.values_mut().zip(next_states) {
+      World::apply(cell, next_state, tally.as_mut());
+    }
+
+    self.tick += 1;
+    self.record(tally);
+  }
+
+  // The same two phases as _tick, but the first is shared out between
+  // the workers a band of rows at a time
+  fn parallel_tick(&mut self) {
+    let columns = (self.width + 1) as usize;
+    let next_states = workers::map_rows((self.height + 1) as usize, self.threads, |y| {
+      (0..=self.width)
+        .map(|x| self.next_state(self.cell_at(x, y as i64).unwrap()))
+        .collect()
+    });
+
+    let mut tally = self.timeline.as_ref().map(|_| Tally::default());
+    for (i, next_state) in next_states.into_iter().enumerate() {
+      let (x, y) = (i % columns, i / columns);
+      let key = format!("{}-{}", x, y);
+      World::apply(
+        self.cells.get_mut(&key).unwrap(),
+        next_state,
+        tally.as_mut(),
+      );
+    }
+
+    self.tick += 1;
+    self.record(tally);
+  }
+
+  // Tallies are only kept while a timeline is, as they cost a little on
+  // every cell
+  fn apply(cell: &mut Cell, next_state: Option<u8>, tally: Option<&mut Tally>) {
+    let (was_alive, decided) = (cell.alive, cell.next_state);
+    cell.apply(next_state);
+    if let Some(tally) = tally {
+      tally.count(
+        (cell.x, cell.y),
+        was_alive,
+        cell.alive,
+        cell.next_state != decided,
+      );
+    }
+  }
+
+  fn record(&mut self, tally: Option<Tally>) {
+    if let (Some(timeline), Some(tally)) = (&mut self.timeline, tally) {
+      timeline.generations.push(tally.finish(self.tick));
+    }
+  }
+
+  /// Starts keeping a [`Timeline`] of statistics for this generation and
+  /// every one played after it. A timeline already being kept carries
+  /// on.
+  pub fn track_timeline(&mut self) {
+    if self.timeline.is_some() {
+      return;
+    }
+
+    let mut tally = Tally::default();
+    for cell in self.cells.values() {
+      tally.count((cell.x, cell.y), cell.alive, cell.alive, false);
+    }
+    self.timeline = Some(Timeline {
+      generations: vec![tally.finish(self.tick)],
+    });
+  }
+
+  /// Returns the timeline being kept, if [`World::track_timeline`] was
+  /// called.
+  pub fn timeline(&self) -> Option<&Timeline> {
+    self.timeline.as_ref()
+  }
+
+  fn next_state(&self, cell: &Cell) -> Option<u8> {
+    let alive_neighbours = self.alive_neighbours_around(cell);
+    if !cell.alive && self.rule.born(alive_neighbours) {
+      Some(1)
+    } else if cell.alive && !self.rule.survives(alive_neighbours) {
+      Some(0)
+    } else {
+      cell.next_state
+    }
+  }
+
+  // Implement first using string concatenation. Then implement any
+  // special string builders, and use whatever runs the fastest
+  /// Renders the world as text, `o` for alive and space for dead, with
+  /// one line per row.
+  pub fn render(&self) -> String {
+    // The following works but is slower
+    // let mut rendering = String::new();
+    // for y in 0..=self.height {
+    //   for x in 0..=self.width {
+    //     let cell = self.cell_at(x, y).unwrap();
+    //     rendering = format!("{}{}", rendering, cell.to_char());
+    //   }
+    //   rendering = format!("{}\n", rendering);
+    // }
+    // rendering
+
+    // The following was the fastest method
+    let mut rendering = String::with_capacity(((self.width + 2) * (self.height + 1)) as usize);
+    for y in 0..=self.height {
+      for x in 0..=self.width {
+        let cell = self.cell_at(x, y).unwrap();
+        rendering.push(cell.to_char());
+      }
+      rendering.push('\n');
+    }
+    rendering
+  }
+
+  fn populate_cells(&mut self, seed: u64, density: f64) {
+    let mut random = Random::new(seed);
+
+    for y in 0..=self.height {
+      for x in 0..=self.width {
+        let alive = random.next_alive(density);
+        self
+          .add_cell(x, y, alive)
+          .expect("each location is only populated once");
+      }
+    }
+  }
+
+  fn prepopulate_neighbours(&mut self) {
+    let keys: Vec<String> = self.cells.keys().cloned().collect();
+    for key in keys {
+      self.neighbours_around(&key);
+    }
+  }
+
+  fn add_cell(&mut self, x: i64, y: i64, alive: bool) -> Result<&Cell, WorldError> {
+    if self.cell_at(x, y).is_some() {
+      return Err(WorldError::LocationOccupied { x, y });
+    }
+
+    let cell = Cell::new(x, y, alive);
+    Ok(self.cells.entry(format!("{}-{}", x, y)).or_insert(cell))
+  }
+
+  /// Returns the cell at a location, if there is one.
+  pub fn cell_at(&self, x: i64, y: i64) -> Option<&Cell> {
+    self.cells.get(&format!("{}-{}", x, y))
+  }
+
+  // Cells can't hold references to each other without fighting the
+  // borrow checker, so neighbours are cached by their keys instead.
+  // The topology decides where offsets past the edges end up
+  fn neighbours_around(&mut self, key: &str) -> &[String] {
+    if self.cells[key].neighbours.is_none() {
+      let cell = &self.cells[key];
+      let neighbours = self
+        .cached_directions
+        .iter()
+        .filter_map(|[rel_x, rel_y]| {
+          self.topology.wrap(
+            cell.x + rel_x,
+            cell.y + rel_y,
+            self.width + 1,
+            self.height + 1,
+          )
+        })
+        .filter(|&(x, y)| self.cell_at(x, y).is_some())
+        .map(|(x, y)| format!("{}-{}", x, y))
+        .collect();
+
+      self.cells.get_mut(key).unwrap().neighbours = Some(neighbours);
+    }
+    self.cells[key].neighbours.as_deref().unwrap()
+  }
+
+  // Implement first using filter/lambda if available. Then implement
+  // foreach and for. Retain whatever implementation runs the fastest
+  /// Counts the living neighbours of a cell.
+  pub fn alive_neighbours_around(&self, cell: &Cell) -> usize {
+    // The following works but is slower
+    // let mut alive_neighbours = 0;
+    // for key in cell.neighbours.iter().flatten() {
+    //   if self.cells[key].alive {
+    //     alive_neighbours += 1;
+    //   }
+    // }
+    // alive_neighbours
+
+    // The following was the fastest method
+    // (neighbours were cached by prepopulate_neighbours, as _tick only
+    // holds a shared borrow of the world here)
+    cell
+      .neighbours
+      .iter()
+      .flatten()
+      .filter(|key| self.cells[*key].alive)
+      .count()
+  }
+}
+
+impl Universe for World {
+  fn width(&self) -> i64 {
+    self.width
+  }
+
+  fn height(&self) -> i64 {
+    self.height
+  }
+
+  fn tick(&self) -> u64 {
+    self.tick
+  }
+
+  fn set_tick(&mut self, tick: u64) {
+    self.tick = tick;
+  }
+
+  fn rule(&self) -> Rule {
+    self.rule
+  }
+
+  fn _tick(&mut self) {
+    World::_tick(self)
+  }
+
+  fn alive_at(&self, x: i64, y: i64) -> bool {
+    self.cell_at(x, y).is_some_and(|cell| cell.alive)
+  }
+
+  fn set_alive(&mut self, x: i64, y: i64, alive: bool) -> Result<(), WorldError> {
+    let key = format!("{}-{}", x, y);
+    let cell = self
+      .cells
+      .get_mut(&key)
+      .ok_or(WorldError::OutOfBounds { x, y })?;
+
+    // Forget the last decision too, or the next tick would repeat it
+    cell.alive = alive;
+    cell.next_state = None;
+    Ok(())
+  }
+
+  fn timeline(&self) -> Option<&Timeline> {
+    World::timeline(self)
+  }
+
+  fn render(&self) -> String {
+    World::render(self)
+  }
+}
+
+// Quadtrees can hold far more than a map of cells ever could, so only
+// universes whose living cells fit in a modest box are flattened
+impl TryFrom<&HashLife> for World {
+  type Error = WorldError;
+
+  /// Flattens a universe into a world cropped to the box around its
+  /// living cells, keeping its rule and generation.
+  fn try_from(hashlife: &HashLife) -> Result<World, WorldError> {
+    let (columns, rows) = hashlife.bounding_box().map_or((1, 1), |bounding_box| {
+      (bounding_box.width(), bounding_box.height())
+    });
+    if columns.saturating_mul(rows) > World::MAX_FLATTENED_CELLS {
+      return Err(WorldError::TooLargeToFlatten { columns, rows });
+    }
+
+    let settings = Settings {
+      rule: hashlife.rule(),
+      ..Settings::default()
+    };
+    let pattern = Pattern::from_universe(hashlife);
+    World::from_pattern(
+      columns as i64 - 1,
+      rows as i64 - 1,
+      settings,
+      &pattern,
+      0,
+      0,
+    )
+  }
+}
+
+/// A single location in a [`World`].
+#[derive(Clone)]
+pub struct Cell {
+  /// Column of the cell.
+  pub x: i64,
+  /// Row of the cell.
+  pub y: i64,
+  /// Whether the cell is currently alive.
+  pub alive: bool,
+  /// State decided by the last tick, `1` for alive and `0` for dead.
+  pub next_state: Option<u8>,
+  /// Keys of the neighbouring cells, once they have been looked up.
+  pub neighbours: Option<Vec<String>>,
+}
+
+impl Cell {
+  /// Creates a cell whose neighbours haven't been looked up yet.
+  pub fn new(x: i64, y: i64, alive: bool) -> Cell {
+    Cell {
+      x,
+      y,
+      alive,
+      next_state: None,
+      neighbours: None,
+    }
+  }
+
+  fn apply(&mut self, next_state: Option<u8>) {
+    self.next_state = next_state;
+    if self.next_state == Some(1) {
+      self.alive = true;
+    } else if self.next_state == Some(0) {
+      self.alive = false;
+    }
+  }
+
+  /// Returns the character `render` uses for this cell.
+  pub fn to_char(&self) -> char {
+    if self.alive {
+      'o'
+    } else {
+      ' '
+    }
+  }
+}
@@ -0,0 +1,134 @@
+use crate::options;
+use crate::Play;
+use gol_core::{Census, Lifespan, Rule};
+use std::path::PathBuf;
+
+pub const USAGE: &str = "Usage: play lifespan --pattern FILE [options]
+
+Plays a pattern on an unbounded plane until it stabilises, and reports
+how long that took, its peak population and what it left behind.
+Escaping spaceships are counted separately and don't hold it up.
+
+Options:
+  --pattern FILE     Pattern to measure, read like play --pattern reads it
+  --rule RULE        Birth/survival rulestring (the pattern's rule,
+                     otherwise B3/S23)
+  --generations N    Give up after this many generations (100000)
+  -h, --help         Show this help
+";
+
+const NAMES: [&str; 3] = ["--pattern", "--rule", "--generations"];
+
+#[derive(Debug, Clone)]
+pub struct LifespanOptions {
+  pub pattern: Option<PathBuf>,
+  pub rule: Option<Rule>,
+  pub generations: u64,
+  pub help: bool,
+}
+
+impl Default for LifespanOptions {
+  fn default() -> LifespanOptions {
+    LifespanOptions {
+      pattern: None,
+      rule: None,
+      generations: 100_000,
+      help: false,
+    }
+  }
+}
+
+impl LifespanOptions {
+  // Accepts both `--name value` and `--name=value`, like Options
+  pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<LifespanOptions, String> {
+    let mut options = LifespanOptions::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+      if arg == "-h" || arg == "--help" {
+        options.help = true;
+        continue;
+      }
+
+      let (name, inline_value) = match arg.split_once('=') {
+        Some((name, value)) => (name.to_string(), Some(value.to_string())),
+        None => (arg.clone(), None),
+      };
+      if !name.starts_with("--") {
+        return Err(format!("Unexpected argument {}", arg));
+      }
+      if !NAMES.contains(&name.as_str()) {
+        return Err(format!("Unknown option {}", name));
+      }
+      let value = match inline_value.or_else(|| args.next()) {
+        Some(value) => value,
+        None => return Err(format!("{} needs a value", name)),
+      };
+
+      match name.as_str() {
+        "--pattern" => options.pattern = Some(PathBuf::from(value)),
+        "--rule" => options.rule = Some(Rule::parse(&value).map_err(|error| error.to_string())?),
+        "--generations" => options.generations = options::number(&name, &value)?,
+        _ => unreachable!("every option name is matched"),
+      }
+    }
+
+    if options.pattern.is_none() && !options.help {
+      return Err("--pattern is needed".to_string());
+    }
+    Ok(options)
+  }
+}
+
+pub fn run(options: &LifespanOptions) -> Result<(), String> {
+  let path = options
+    .pattern
+    .as_ref()
+    .expect("parse checks for a pattern");
+  let pattern = Play::load_pattern(path)?;
+  // A rule given on the command line wins over the pattern's own
+  let rule = options.rule.or(pattern.rule).unwrap_or_default();
+  let lifespan =
+    Lifespan::measure(&pattern, rule, options.generations).map_err(|error| error.to_string())?;
+
+  match lifespan.cycle {
+    Some(_) if lifespan.final_population == 0 && lifespan.escaped.is_empty() => {
+      println!("Died out at generation {}", lifespan.final_generation)
+    }
+    // Describing it as a still life would hide that something got away
+    Some(_) if lifespan.final_population == 0 => println!(
+      "Nothing left but escaping spaceships from generation {}",
+      lifespan.final_generation
+    ),
+    Some(cycle) => println!("{}", Play::describe(cycle)),
+    None => println!(
+      "Still hadn't stabilised at generation {}",
+      lifespan.final_generation
+    ),
+  }
+  println!(
+    "Peak population {} at generation {}",
+    lifespan.peak_population, lifespan.peak_generation
+  );
+  match lifespan.bounding_box {
+    Some(bounding_box) => println!(
+      "Final population {} in {}x{} cells from {},{}",
+      lifespan.final_population,
+      bounding_box.width(),
+      bounding_box.height(),
+      bounding_box.min_x,
+      bounding_box.min_y
+    ),
+    None => println!("Final population 0"),
+  }
+
+  // The census already names objects and counts them
+  if !lifespan.escaped.is_empty() {
+    let escaped = Census {
+      generation: lifespan.final_generation,
+      islands: lifespan.escaped,
+    };
+    println!("Escaped: {}", escaped);
+  }
+  Ok(())
+}
@@ -0,0 +1,512 @@
+mod lifespan;
+mod options;
+mod search;
+mod terminal;
+mod tui;
+
+use gol_core::{
+  BitWorld, BrailleRenderer, Census, Connectivity, Cycle, CycleDetector, Frame, HalfBlockRenderer,
+  HashLife, ImageOptions, Pattern, Random, Renderer, Settings, SparseWorld, TextRenderer, Universe,
+  World,
+};
+use lifespan::LifespanOptions;
+use options::{Backend, Options, Output, RenderStyle, USAGE};
+use search::SearchOptions;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use std::{env, fs, io, process, thread};
+use terminal::Terminal;
+
+struct Play;
+
+impl Play {
+  // Generations timed on a single thread to work out the speed-up
+  const BASELINE_TICKS: u64 = 20;
+  // Generations charted by --sparkline
+  const SPARKLINE_WIDTH: usize = 40;
+
+  fn run(options: &Options) -> Result<(), String> {
+    // Pick the seed here, so the baseline plays the same soup
+    let seed = options.seed.unwrap_or_else(Random::clock_seed);
+    let macrocell = options
+      .pattern
+      .as_deref()
+      .filter(|path| options.backend == Backend::HashLife && Play::is_macrocell(path));
+    let (pattern, mut world) = match (macrocell, &options.pattern) {
+      (Some(path), _) => (None, Play::load_macrocell(options, path)?),
+      (None, Some(path)) => {
+        let pattern = Play::load_pattern(path)?;
+        let world = Play::build(options, Some(&pattern), seed, options.threads)?;
+        (Some(pattern), world)
+      }
+      (None, None) => (None, Play::build(options, None, seed, options.threads)?),
+    };
+    if options.output == Output::Tui {
+      return tui::run(options, world);
+    }
+    let baseline_tick = Play::baseline_tick(options, pattern.as_ref(), seed)?;
+    let frame_time = options.fps.map(|fps| Duration::from_secs_f64(1.0 / fps));
+
+    let renderer: Box<dyn Renderer> = match options.renderer {
+      RenderStyle::Text => Box::new(TextRenderer),
+      RenderStyle::HalfBlock => Box::new(HalfBlockRenderer),
+      RenderStyle::Braille => Box::new(BrailleRenderer),
+    };
+
+    let mut terminal = Terminal::default();
+    match options.output {
+      Output::Interactive => {
+        let update = terminal.update(&renderer.render(world.as_ref()));
+        Play::write(&format!("\u{001b}[H\u{001b}[2J#{}{}", world.tick(), update))?;
+      }
+      Output::Redraw => println!("{}", renderer.render(world.as_ref())),
+      Output::Stats | Output::None | Output::Tui => {}
+    }
+
+    let image = ImageOptions {
+      cell_size: options.cell_size,
+      ..ImageOptions::default()
+    };
+    let mut frames = Vec::new();
+    Play::record(options, &image, world.as_ref(), &mut frames)?;
+
+    // Patterns saved from a frame carry on from its generation, so the
+    // averages count the ticks played here rather than world.tick()
+    let mut played = 0;
+    let mut total_tick = 0.0;
+    let mut total_render = 0.0;
+    let mut detector = options.stop_on_cycle.then(CycleDetector::new);
+    if let Some(detector) = &mut detector {
+      detector.observe(world.as_ref());
+    }
+
+    while options
+      .generations
+      .is_none_or(|generations| world.tick() < generations)
+    {
+      let frame_start = Instant::now();
+
+      let tick_start = Instant::now();
+      world._tick();
+      let tick_time = tick_start.elapsed().as_secs_f64() * 1000.0;
+      played += 1;
+      total_tick += tick_time;
+      let avg_tick = total_tick / played as f64;
+
+      let render_start = Instant::now();
+      let rendered = renderer.render(world.as_ref());
+      let render_time = render_start.elapsed().as_secs_f64() * 1000.0;
+      total_render += render_time;
+      let avg_render = total_render / played as f64;
+
+      Play::record(options, &image, world.as_ref(), &mut frames)?;
+
+      let mut output = format!("#{}", world.tick());
+      output += &format!(
+        " - World tick took {} ({})",
+        Play::_f(tick_time),
+        Play::_f(avg_tick)
+      );
+      output += &format!(
+        " - Rendering took {} ({})",
+        Play::_f(render_time),
+        Play::_f(avg_render)
+      );
+      if let Some(baseline_tick) = baseline_tick {
+        output += &format!(
+          " - {} threads sped up ticks {}x",
+          options.threads,
+          Play::_f(baseline_tick / avg_tick)
+        );
+      }
+      if let Some(sparkline) = Play::sparkline(options, world.as_ref()) {
+        output += &format!(" - {}", sparkline);
+      }
+
+      // Bytes are counted for the world only, as the stats line can't
+      // count itself
+      match options.output {
+        Output::Interactive => {
+          let update = terminal.update(&rendered);
+          output += &format!(" - Wrote {} bytes", update.len());
+          Play::write(&format!("\u{001b}[H{}\u{001b}[K{}", output, update))?;
+        }
+        Output::Redraw => {
+          let clear = "\u{001b}[H\u{001b}[2J";
+          output += &format!(" - Wrote {} bytes", clear.len() + rendered.len());
+          println!("{}{}\n{}", clear, output, rendered);
+        }
+        Output::Stats => println!("{}", output),
+        Output::None | Output::Tui => {}
+      }
+
+      let cycle = detector
+        .as_mut()
+        .and_then(|detector| detector.observe(world.as_ref()));
+      if let Some(cycle) = cycle {
+        if options.output != Output::None {
+          println!("{}", Play::describe(cycle));
+        }
+        break;
+      }
+
+      if let Some(frame_time) = frame_time {
+        thread::sleep(frame_time.saturating_sub(frame_start.elapsed()));
+      }
+    }
+
+    if options.census {
+      let census = Census::new(world.as_ref(), Connectivity::default());
+      println!("Census at generation {}: {}", census.generation, census);
+      for spaceship in census.spaceships() {
+        let (x, y) = spaceship.position();
+        println!(
+          "  {} at {},{}: {}",
+          spaceship.name().unwrap_or(&spaceship.code),
+          x,
+          y,
+          spaceship
+            .velocity
+            .map_or(String::new(), |velocity| velocity.to_string())
+        );
+      }
+    }
+
+    if let Some(path) = &options.record {
+      Play::save_recording(path, &image, &frames)?;
+    }
+    match &options.timeline {
+      Some(path) => Play::save_timeline(path, world.as_ref()),
+      None => Ok(()),
+    }
+  }
+
+  fn sparkline(options: &Options, world: &dyn Universe) -> Option<String> {
+    let timeline = world.timeline().filter(|_| options.sparkline)?;
+    Some(timeline.sparkline(Play::SPARKLINE_WIDTH))
+  }
+
+  fn describe(cycle: Cycle) -> String {
+    if cycle.is_still() {
+      format!("Settled into a still life at generation {}", cycle.start)
+    } else {
+      format!(
+        "Settled into a period {} cycle from generation {}",
+        cycle.period, cycle.start
+      )
+    }
+  }
+
+  // Updates don't end in a newline, so they are flushed by hand
+  fn write(output: &str) -> Result<(), String> {
+    let mut stdout = io::stdout().lock();
+    stdout
+      .write_all(output.as_bytes())
+      .and_then(|_| stdout.flush())
+      .map_err(|error| format!("Couldn't write to the terminal: {}", error))
+  }
+
+  // Frames are only drawn when they are going to be saved
+  fn record(
+    options: &Options,
+    image: &ImageOptions,
+    world: &dyn Universe,
+    frames: &mut Vec<Frame>,
+  ) -> Result<(), String> {
+    if options.record.is_some() {
+      frames.push(image.frame(world).map_err(|error| error.to_string())?);
+    }
+    Ok(())
+  }
+
+  // GIF is the default, as every browser and chat app plays it
+  fn save_recording(path: &Path, image: &ImageOptions, frames: &[Frame]) -> Result<(), String> {
+    let is_png = path
+      .extension()
+      .and_then(|extension| extension.to_str())
+      .is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+    let bytes = if is_png {
+      image.apng(frames)
+    } else {
+      image.gif(frames)
+    };
+    fs::write(path, bytes).map_err(|error| format!("Couldn't write {}: {}", path.display(), error))
+  }
+
+  // CSV is the default, as spreadsheets and plotting tools all read it
+  fn save_timeline(path: &Path, world: &dyn Universe) -> Result<(), String> {
+    let Some(timeline) = world.timeline() else {
+      return Ok(());
+    };
+    let is_json = path
+      .extension()
+      .and_then(|extension| extension.to_str())
+      .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    let text = if is_json {
+      timeline.to_json()
+    } else {
+      timeline.to_csv()
+    };
+    fs::write(path, text).map_err(|error| format!("Couldn't write {}: {}", path.display(), error))
+  }
+
+  fn build(
+    options: &Options,
+    pattern: Option<&Pattern>,
+    seed: u64,
+    threads: usize,
+  ) -> Result<Box<dyn Universe>, String> {
+    let settings = Settings {
+      // A rule given on the command line wins over the pattern's own
+      rule: options
+        .rule
+        .or(pattern.and_then(|pattern| pattern.rule))
+        .unwrap_or_default(),
+      topology: options.topology,
+      threads,
+      seed: Some(seed),
+      // A pattern starts from an empty world
+      density: if pattern.is_some() {
+        0.0
+      } else {
+        options.density
+      },
+    };
+
+    let (width, height) = (options.width, options.height);
+    let (x, y) = options.offset;
+    let mut world: Box<dyn Universe> = match (options.backend, pattern) {
+      (Backend::Naive, pattern) => {
+        // World places patterns itself, cell by cell as it is built
+        let mut world = match pattern {
+          Some(pattern) => World::from_pattern(width, height, settings, pattern, x, y)
+            .map_err(|error| error.to_string())?,
+          None => {
+            World::with_settings(width, height, settings).map_err(|error| error.to_string())?
+          }
+        };
+        if options.tracks_timeline() {
+          world.track_timeline();
+        }
+        Box::new(world)
+      }
+      (Backend::Bits, _) => Box::new(
+        BitWorld::with_settings(width, height, settings).map_err(|error| error.to_string())?,
+      ),
+      (Backend::HashLife, _) => Box::new(
+        HashLife::with_settings(width, height, settings).map_err(|error| error.to_string())?,
+      ),
+      (Backend::Sparse, _) => Box::new(
+        SparseWorld::with_settings(width, height, settings).map_err(|error| error.to_string())?,
+      ),
+    };
+
+    // The other backends start empty and have the pattern drawn in
+    if let Some(pattern) = pattern.filter(|_| options.backend != Backend::Naive) {
+      pattern
+        .place(world.as_mut(), x, y)
+        .map_err(|error| error.to_string())?;
+      world.set_tick(pattern.generation);
+    }
+    Ok(world)
+  }
+
+  // Pattern files are recognised by their extension. Anything else is
+  // read as a frame printed by render, from this or another language
+  fn load_pattern(path: &Path) -> Result<Pattern, String> {
+    let text = fs::read_to_string(path)
+      .map_err(|error| format!("Couldn't read {}: {}", path.display(), error))?;
+
+    let extension = path
+      .extension()
+      .and_then(|extension| extension.to_str())
+      .map(|extension| extension.to_ascii_lowercase());
+    let parsed = match extension.as_deref() {
+      Some("rle") => Pattern::from_rle(&text),
+      Some("cells") => Pattern::from_plaintext(&text),
+      Some("lif" | "life") => Pattern::from_life(&text),
+      // Macrocells are flattened for backends other than HashLife, so
+      // only patterns of a sensible size can be played by them
+      Some("mc") => HashLife::from_macrocell(0, 0, &text)
+        .and_then(|hashlife| World::try_from(&hashlife))
+        .map(|world| Pattern::from_universe(&world)),
+      _ => Pattern::from_render(&text),
+    };
+    parsed.map_err(|error| format!("{}: {}", path.display(), error))
+  }
+
+  fn is_macrocell(path: &Path) -> bool {
+    path
+      .extension()
+      .and_then(|extension| extension.to_str())
+      .is_some_and(|extension| extension.eq_ignore_ascii_case("mc"))
+  }
+
+  // HashLife plays a macrocell as the quadtree it already is, however
+  // big. The viewport moves instead of the cells, so the pattern's top
+  // left corner still shows up at --offset
+  fn load_macrocell(options: &Options, path: &Path) -> Result<Box<dyn Universe>, String> {
+    let text = fs::read_to_string(path)
+      .map_err(|error| format!("Couldn't read {}: {}", path.display(), error))?;
+    let mut hashlife = HashLife::from_macrocell(options.width, options.height, &text)
+      .map_err(|error| format!("{}: {}", path.display(), error))?;
+    if let Some(rule) = options.rule {
+      hashlife.set_rule(rule).map_err(|error| error.to_string())?;
+    }
+
+    if let Some(bounding_box) = hashlife.bounding_box() {
+      let (x, y) = options.offset;
+      let (Some(left), Some(top)) = (
+        bounding_box.min_x.checked_sub(x),
+        bounding_box.min_y.checked_sub(y),
+      ) else {
+        return Err(format!(
+          "{}: --offset moves it off the plane",
+          path.display()
+        ));
+      };
+      hashlife.set_viewport(left, top, options.width, options.height);
+    }
+    Ok(Box::new(hashlife))
+  }
+
+  // Saves in the format the extension names, the way load_pattern reads
+  // them. Life 1.05 goes in .lif files and Life 1.06 in .life files
+  fn save_pattern(path: &Path, universe: &dyn Universe) -> Result<(), String> {
+    let pattern = Pattern::from_universe(universe);
+    let extension = path
+      .extension()
+      .and_then(|extension| extension.to_str())
+      .map(|extension| extension.to_ascii_lowercase());
+    let text = match extension.as_deref() {
+      Some("rle") => pattern.to_rle(),
+      Some("cells") => pattern.to_plaintext(),
+      Some("lif") => pattern.to_life105(),
+      Some("life") => pattern.to_life106(),
+      // Macrocells are written by HashLife, so the pattern goes there
+      Some("mc") => {
+        let settings = Settings {
+          rule: universe.rule(),
+          density: 0.0,
+          ..Settings::default()
+        };
+        let mut hashlife = HashLife::with_settings(0, 0, settings)
+          .map_err(|error| format!("{}: {}", path.display(), error))?;
+        pattern
+          .place(&mut hashlife, 0, 0)
+          .map_err(|error| format!("{}: {}", path.display(), error))?;
+        hashlife.set_tick(pattern.generation);
+        hashlife.to_macrocell()
+      }
+      _ => {
+        return Err(format!(
+          "{}: patterns are saved as .rle, .cells, .lif, .life or .mc",
+          path.display()
+        ))
+      }
+    };
+    fs::write(path, text).map_err(|error| format!("Couldn't write {}: {}", path.display(), error))
+  }
+
+  // Times a single threaded copy of the world, as there is no speed-up
+  // to report when the world already plays on one thread
+  fn baseline_tick(
+    options: &Options,
+    pattern: Option<&Pattern>,
+    seed: u64,
+  ) -> Result<Option<f64>, String> {
+    let threaded = matches!(options.backend, Backend::Naive | Backend::Bits);
+    if options.threads <= 1 || !threaded {
+      return Ok(None);
+    }
+
+    let mut baseline = Play::build(options, pattern, seed, 1)?;
+    let start = Instant::now();
+    for _ in 0..Play::BASELINE_TICKS {
+      baseline._tick();
+    }
+    Ok(Some(
+      start.elapsed().as_secs_f64() * 1000.0 / Play::BASELINE_TICKS as f64,
+    ))
+  }
+
+  fn _f(value: f64) -> String {
+    format!("{:.3}", value)
+  }
+}
+
+fn main() {
+  let mut args: Vec<String> = env::args().skip(1).collect();
+  match args.first().map(String::as_str) {
+    Some("search") => {
+      args.remove(0);
+      search_main(args);
+      return;
+    }
+    Some("lifespan") => {
+      args.remove(0);
+      lifespan_main(args);
+      return;
+    }
+    _ => {}
+  }
+
+  let options = match Options::parse(args) {
+    Ok(options) => options,
+    Err(message) => {
+      eprintln!("{}\n\n{}", message, USAGE);
+      process::exit(2);
+    }
+  };
+
+  if options.help {
+    print!("{}", USAGE);
+    return;
+  }
+
+  if let Err(message) = Play::run(&options) {
+    eprintln!("Error: {}", message);
+    process::exit(1);
+  }
+}
+
+fn search_main(args: Vec<String>) {
+  let options = match SearchOptions::parse(args) {
+    Ok(options) => options,
+    Err(message) => {
+      eprintln!("{}\n\n{}", message, search::USAGE);
+      process::exit(2);
+    }
+  };
+
+  if options.help {
+    print!("{}", search::USAGE);
+    return;
+  }
+
+  if let Err(message) = search::run(&options) {
+    eprintln!("Error: {}", message);
+    process::exit(1);
+  }
+}
+
+fn lifespan_main(args: Vec<String>) {
+  let options = match LifespanOptions::parse(args) {
+    Ok(options) => options,
+    Err(message) => {
+      eprintln!("{}\n\n{}", message, lifespan::USAGE);
+      process::exit(2);
+    }
+  };
+
+  if options.help {
+    print!("{}", lifespan::USAGE);
+    return;
+  }
+
+  if let Err(message) = lifespan::run(&options) {
+    eprintln!("Error: {}", message);
+    process::exit(1);
+  }
+}
@@ -0,0 +1,301 @@
+use gol_core::{Rule, Topology};
+use std::path::PathBuf;
+
+pub const USAGE: &str = "Usage: play [options]
+       play search [options], see play search --help
+       play lifespan --pattern FILE, see play lifespan --help
+
+Options:
+  --width N          Right-most column of the world (150)
+  --height N         Bottom row of the world (40)
+  --seed N           Seed of the random soup (taken from the clock)
+  --density F        Chance of each soup cell starting alive (0.2)
+  --rule RULE        Birth/survival rulestring, such as B36/S23 (the
+                     pattern's rule, otherwise B3/S23)
+  --topology NAME    bounded, torus, klein or cross (bounded)
+  --backend NAME     naive, bits, hashlife or sparse (naive)
+  --threads N        Worker threads playing each tick (1)
+  --generations N    Stop after this many generations (never)
+  --fps N            Target frames per second (as fast as possible)
+  --pattern FILE     Start from a pattern instead of a random soup, read
+                     as RLE, plaintext, Life 1.05/1.06 or macrocell when
+                     the file ends in .rle, .cells, .lif or .mc, and
+                     otherwise as a frame printed by any implementation.
+                     hashlife plays macrocells of any size as they are
+  --offset X,Y       Where the pattern's top left corner goes (0,0)
+  --output MODE      interactive, redraw, stats, none or tui, which plays
+                     full screen with keys to pause, step, edit, pan,
+                     zoom, open and save (interactive)
+  --renderer NAME    text, half or braille, drawing 1, 2 or 8 cells per
+                     character (text)
+  --record FILE      Save every generation played as an animated GIF, or
+                     as an animated PNG when FILE ends in .png (needs
+                     --generations)
+  --cell-size N      Pixels across each cell in a recording (4)
+  --stop-on-cycle    Stop once the world repeats itself, and report the
+                     generation the cycle started and its period
+  --census           Count the objects left on the board when play stops,
+                     and list the spaceships with their speed and heading
+  --timeline FILE    Save population, births, deaths, bounding box and
+                     activity for every generation, as JSON when FILE
+                     ends in .json and otherwise as CSV (naive backend)
+  --sparkline        Chart the population in the stats line (naive
+                     backend)
+  -h, --help         Show this help
+";
+
+const NAMES: [&str; 17] = [
+  "--width",
+  "--height",
+  "--seed",
+  "--density",
+  "--rule",
+  "--topology",
+  "--backend",
+  "--threads",
+  "--generations",
+  "--fps",
+  "--pattern",
+  "--offset",
+  "--output",
+  "--renderer",
+  "--record",
+  "--cell-size",
+  "--timeline",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+  Naive,
+  Bits,
+  HashLife,
+  Sparse,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+  // Draw the stats line and only the cells that changed every tick
+  Interactive,
+  // Clear the screen and draw the stats line and the world every tick,
+  // which is what frames read back as patterns expect
+  Redraw,
+  // Play full screen under the user's control
+  Tui,
+  // Only print the stats line, one per tick
+  Stats,
+  // Print nothing at all
+  None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderStyle {
+  // One cell per character, o or space
+  Text,
+  // Two rows per character with half blocks
+  HalfBlock,
+  // Two columns and four rows per character with braille patterns
+  Braille,
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+  pub width: i64,
+  pub height: i64,
+  pub seed: Option<u64>,
+  pub density: f64,
+  pub rule: Option<Rule>,
+  pub topology: Topology,
+  pub backend: Backend,
+  pub threads: usize,
+  pub generations: Option<u64>,
+  pub fps: Option<f64>,
+  pub pattern: Option<PathBuf>,
+  pub offset: (i64, i64),
+  pub output: Output,
+  pub renderer: RenderStyle,
+  pub record: Option<PathBuf>,
+  pub cell_size: u32,
+  pub stop_on_cycle: bool,
+  pub census: bool,
+  pub timeline: Option<PathBuf>,
+  pub sparkline: bool,
+  pub help: bool,
+}
+
+impl Default for Options {
+  fn default() -> Options {
+    Options {
+      width: 150,
+      height: 40,
+      seed: None,
+      density: 0.2,
+      rule: None,
+      topology: Topology::Bounded,
+      backend: Backend::Naive,
+      threads: 1,
+      generations: None,
+      fps: None,
+      pattern: None,
+      offset: (0, 0),
+      output: Output::Interactive,
+      renderer: RenderStyle::Text,
+      record: None,
+      cell_size: 4,
+      stop_on_cycle: false,
+      census: false,
+      timeline: None,
+      sparkline: false,
+      help: false,
+    }
+  }
+}
+
+impl Options {
+  // Accepts both `--name value` and `--name=value`
+  pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+      if arg == "-h" || arg == "--help" {
+        options.help = true;
+        continue;
+      }
+      if arg == "--stop-on-cycle" {
+        options.stop_on_cycle = true;
+        continue;
+      }
+      if arg == "--census" {
+        options.census = true;
+        continue;
+      }
+      if arg == "--sparkline" {
+        options.sparkline = true;
+        continue;
+      }
+
+      let (name, inline_value) = match arg.split_once('=') {
+        Some((name, value)) => (name.to_string(), Some(value.to_string())),
+        None => (arg.clone(), None),
+      };
+      if !name.starts_with("--") {
+        return Err(format!("Unexpected argument {}", arg));
+      }
+      if !NAMES.contains(&name.as_str()) {
+        return Err(format!("Unknown option {}", name));
+      }
+      let value = match inline_value.or_else(|| args.next()) {
+        Some(value) => value,
+        None => return Err(format!("{} needs a value", name)),
+      };
+
+      match name.as_str() {
+        "--width" => options.width = number(&name, &value)?,
+        "--height" => options.height = number(&name, &value)?,
+        "--seed" => options.seed = Some(number(&name, &value)?),
+        "--density" => options.density = number(&name, &value)?,
+        "--rule" => options.rule = Some(Rule::parse(&value).map_err(|error| error.to_string())?),
+        "--topology" => options.topology = topology(&value)?,
+        "--backend" => options.backend = backend(&value)?,
+        "--threads" => options.threads = number(&name, &value)?,
+        "--generations" => options.generations = Some(number(&name, &value)?),
+        "--fps" => options.fps = Some(number(&name, &value)?),
+        "--pattern" => options.pattern = Some(PathBuf::from(value)),
+        "--offset" => options.offset = offset(&value)?,
+        "--output" => options.output = output(&value)?,
+        "--renderer" => options.renderer = renderer(&value)?,
+        "--record" => options.record = Some(PathBuf::from(value)),
+        "--cell-size" => options.cell_size = number(&name, &value)?,
+        "--timeline" => options.timeline = Some(PathBuf::from(value)),
+        _ => unreachable!("every option name is matched"),
+      }
+    }
+
+    if options.width < 0 || options.height < 0 {
+      return Err("--width and --height can't be negative".to_string());
+    }
+    if !(0.0..=1.0).contains(&options.density) {
+      return Err("--density must be between 0 and 1".to_string());
+    }
+    if options.fps.is_some_and(|fps| fps <= 0.0) {
+      return Err("--fps must be above 0".to_string());
+    }
+    // Frames are kept in memory until the file is written at the end
+    if options.record.is_some() && options.generations.is_none() {
+      return Err("--record needs --generations".to_string());
+    }
+    if options.record.is_some() && options.output == Output::Tui {
+      return Err("--record can't be used with --output tui".to_string());
+    }
+    if options.cell_size == 0 {
+      return Err("--cell-size must be above 0".to_string());
+    }
+    // Only World keeps statistics as it plays
+    if options.tracks_timeline() && options.backend != Backend::Naive {
+      return Err("--timeline and --sparkline need --backend naive".to_string());
+    }
+    if options.timeline.is_some() && options.output == Output::Tui {
+      return Err("--timeline can't be used with --output tui".to_string());
+    }
+    Ok(options)
+  }
+
+  pub fn tracks_timeline(&self) -> bool {
+    self.timeline.is_some() || self.sparkline
+  }
+}
+
+pub fn number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+  value
+    .parse()
+    .map_err(|_| format!("{} expects a number, not {}", name, value))
+}
+
+fn offset(value: &str) -> Result<(i64, i64), String> {
+  let invalid = || format!("--offset expects X,Y, not {}", value);
+  let (x, y) = value.split_once(',').ok_or_else(invalid)?;
+  match (x.trim().parse(), y.trim().parse()) {
+    (Ok(x), Ok(y)) => Ok((x, y)),
+    _ => Err(invalid()),
+  }
+}
+
+fn topology(value: &str) -> Result<Topology, String> {
+  match value {
+    "bounded" => Ok(Topology::Bounded),
+    "torus" => Ok(Topology::Torus),
+    "klein" => Ok(Topology::KleinBottle),
+    "cross" => Ok(Topology::CrossSurface),
+    _ => Err(format!("Unknown topology {}", value)),
+  }
+}
+
+pub fn backend(value: &str) -> Result<Backend, String> {
+  match value {
+    "naive" => Ok(Backend::Naive),
+    "bits" => Ok(Backend::Bits),
+    "hashlife" => Ok(Backend::HashLife),
+    "sparse" => Ok(Backend::Sparse),
+    _ => Err(format!("Unknown backend {}", value)),
+  }
+}
+
+fn renderer(value: &str) -> Result<RenderStyle, String> {
+  match value {
+    "text" => Ok(RenderStyle::Text),
+    "half" => Ok(RenderStyle::HalfBlock),
+    "braille" => Ok(RenderStyle::Braille),
+    _ => Err(format!("Unknown renderer {}", value)),
+  }
+}
+
+fn output(value: &str) -> Result<Output, String> {
+  match value {
+    "interactive" => Ok(Output::Interactive),
+    "redraw" => Ok(Output::Redraw),
+    "tui" => Ok(Output::Tui),
+    "stats" => Ok(Output::Stats),
+    "none" => Ok(Output::None),
+    _ => Err(format!("Unknown output mode {}", value)),
+  }
+}
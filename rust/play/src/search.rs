@@ -0,0 +1,237 @@
+use crate::options::{self, Backend};
+use gol_core::{object_name, Rule, Search, SearchResults};
+use std::path::{Path, PathBuf};
+use std::{fs, thread};
+
+pub const USAGE: &str = "Usage: play search [options]
+
+Plays random soups until they settle, one per seed, and counts the
+objects they leave behind the way apgsearch does.
+
+Options:
+  --seed N           First seed to search (0)
+  --soups N          Number of soups to search (1000)
+  --size N           Cells across and down each soup (16)
+  --density F        Chance of each soup cell starting alive (0.5)
+  --rule RULE        Birth/survival rulestring (B3/S23)
+  --margin N         Empty cells around each soup, soups spreading past
+                     it are set aside as unsettled with naive (128)
+  --backend NAME     naive, or sparse which is much faster and plays on
+                     the unbounded plane, so soups have no edge to
+                     spread to (naive)
+  --generations N    Generations a soup may play before it is set aside
+                     as unsettled (10000)
+  --threads N        Soups played at once (one per CPU)
+  --checkpoint FILE  Save the results here as the search goes, and carry
+                     on from them when the file already exists
+  --rare N           List the seeds of objects found fewer than N times
+                     (10)
+  -h, --help         Show this help
+";
+
+const NAMES: [&str; 11] = [
+  "--seed",
+  "--soups",
+  "--size",
+  "--density",
+  "--rule",
+  "--margin",
+  "--backend",
+  "--generations",
+  "--threads",
+  "--checkpoint",
+  "--rare",
+];
+
+// Soups each thread plays between checkpoints
+const BATCH: u64 = 16;
+
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+  pub seed: u64,
+  pub soups: u64,
+  pub search: Search,
+  pub checkpoint: Option<PathBuf>,
+  pub rare: u64,
+  pub help: bool,
+}
+
+impl Default for SearchOptions {
+  fn default() -> SearchOptions {
+    SearchOptions {
+      seed: 0,
+      soups: 1000,
+      search: Search {
+        threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+        ..Search::default()
+      },
+      checkpoint: None,
+      rare: 10,
+      help: false,
+    }
+  }
+}
+
+impl SearchOptions {
+  // Accepts both `--name value` and `--name=value`, like Options
+  pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<SearchOptions, String> {
+    let mut options = SearchOptions::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+      if arg == "-h" || arg == "--help" {
+        options.help = true;
+        continue;
+      }
+
+      let (name, inline_value) = match arg.split_once('=') {
+        Some((name, value)) => (name.to_string(), Some(value.to_string())),
+        None => (arg.clone(), None),
+      };
+      if !name.starts_with("--") {
+        return Err(format!("Unexpected argument {}", arg));
+      }
+      if !NAMES.contains(&name.as_str()) {
+        return Err(format!("Unknown option {}", name));
+      }
+      let value = match inline_value.or_else(|| args.next()) {
+        Some(value) => value,
+        None => return Err(format!("{} needs a value", name)),
+      };
+
+      let search = &mut options.search;
+      match name.as_str() {
+        "--seed" => options.seed = options::number(&name, &value)?,
+        "--soups" => options.soups = options::number(&name, &value)?,
+        "--size" => search.soup_size = options::number(&name, &value)?,
+        "--density" => search.density = options::number(&name, &value)?,
+        "--rule" => search.rule = Rule::parse(&value).map_err(|error| error.to_string())?,
+        "--margin" => search.margin = options::number(&name, &value)?,
+        "--backend" => {
+          search.unbounded = match options::backend(&value)? {
+            Backend::Naive => false,
+            Backend::Sparse => true,
+            _ => return Err("search plays soups with --backend naive or sparse".to_string()),
+          }
+        }
+        "--generations" => search.max_generations = options::number(&name, &value)?,
+        "--threads" => search.threads = options::number(&name, &value)?,
+        "--checkpoint" => options.checkpoint = Some(PathBuf::from(value)),
+        "--rare" => options.rare = options::number(&name, &value)?,
+        _ => unreachable!("every option name is matched"),
+      }
+    }
+
+    if options.search.soup_size <= 0 {
+      return Err("--size must be above 0".to_string());
+    }
+    if options.search.margin < 0 {
+      return Err("--margin can't be negative".to_string());
+    }
+    if !(0.0..=1.0).contains(&options.search.density) {
+      return Err("--density must be between 0 and 1".to_string());
+    }
+    if options.seed.checked_add(options.soups).is_none() {
+      return Err("--seed and --soups go past the last seed".to_string());
+    }
+    Ok(options)
+  }
+}
+
+pub fn run(options: &SearchOptions) -> Result<(), String> {
+  let search = &options.search;
+  let mut results = match &options.checkpoint {
+    Some(path) if path.exists() => resume(path, options)?,
+    _ => SearchResults::new(options.seed),
+  };
+
+  // Every batch is saved, so an interrupted search loses one at most
+  let end = options.seed + options.soups;
+  let batch = BATCH * search.threads.max(1) as u64;
+  while results.next_seed < end {
+    let soups = batch.min(end - results.next_seed);
+    search
+      .run(&mut results, soups)
+      .map_err(|error| error.to_string())?;
+    if let Some(path) = &options.checkpoint {
+      fs::write(path, results.to_checkpoint(search))
+        .map_err(|error| format!("Couldn't write {}: {}", path.display(), error))?;
+    }
+    println!(
+      "Searched {} of {} soups, {} unsettled",
+      results.soups(),
+      options.soups,
+      results.unsettled.len()
+    );
+  }
+
+  report(&results, options.rare);
+  Ok(())
+}
+
+// A checkpoint carries on the same range of seeds, so it has to start
+// where this search does
+fn resume(path: &Path, options: &SearchOptions) -> Result<SearchResults, String> {
+  let text = fs::read_to_string(path)
+    .map_err(|error| format!("Couldn't read {}: {}", path.display(), error))?;
+  let results = SearchResults::from_checkpoint(&text, &options.search)
+    .map_err(|error| format!("{}: {}", path.display(), error))?;
+  if results.first_seed != options.seed {
+    return Err(format!(
+      "{}: started from seed {}, not {}",
+      path.display(),
+      results.first_seed,
+      options.seed
+    ));
+  }
+
+  println!(
+    "Carrying on from seed {} with {} soups searched",
+    results.next_seed,
+    results.soups()
+  );
+  Ok(results)
+}
+
+fn report(results: &SearchResults, rare: u64) {
+  println!(
+    "\nSearched {} soups from seed {}",
+    results.soups(),
+    results.first_seed
+  );
+  for (code, count) in results.common() {
+    println!("{:>10}  {}", count, describe(code));
+  }
+
+  let rare = results.rare(rare);
+  if !rare.is_empty() {
+    println!("\nRare objects:");
+    for (code, count, seeds) in rare {
+      println!(
+        "{:>10}  {} from {} {}",
+        count,
+        describe(code),
+        if seeds.len() == 1 { "seed" } else { "seeds" },
+        list(seeds)
+      );
+    }
+  }
+  if !results.unsettled.is_empty() {
+    println!("\nUnsettled soups: {}", list(&results.unsettled));
+  }
+}
+
+fn describe(code: &str) -> String {
+  match object_name(code) {
+    Some(name) => format!("{} ({})", code, name),
+    None => code.to_string(),
+  }
+}
+
+fn list(seeds: &[u64]) -> String {
+  seeds
+    .iter()
+    .map(|seed| seed.to_string())
+    .collect::<Vec<String>>()
+    .join(", ")
+}
@@ -0,0 +1,68 @@
+use std::fmt::Write;
+
+// Keeps the frame on screen and works out the bytes that turn it into
+// the next one, so the terminal only redraws the cells that changed.
+// Rows are drawn from the second line down, under the stats line, on a
+// screen that starts out cleared
+#[derive(Debug, Default)]
+pub struct Terminal {
+  previous: Vec<Vec<char>>,
+}
+
+impl Terminal {
+  // Moving the cursor takes about this many bytes, so shorter gaps
+  // between changed cells are cheaper to write out again
+  const GAP: usize = 6;
+
+  pub fn update(&mut self, rendered: &str) -> String {
+    let rows: Vec<Vec<char>> = rendered
+      .lines()
+      .map(|line| line.chars().collect())
+      .collect();
+
+    let mut output = String::new();
+    for y in 0..rows.len().max(self.previous.len()) {
+      let row = rows.get(y).map_or(&[][..], Vec::as_slice);
+      let previous = self.previous.get(y).map_or(&[][..], Vec::as_slice);
+      Terminal::update_row(&mut output, y + 2, previous, row);
+    }
+
+    // Leave the cursor under the frame, where the prompt should go
+    let _ = write!(output, "\u{001b}[{};1H", rows.len() + 2);
+    self.previous = rows;
+    output
+  }
+
+  // Cells past the end of either row are blank on screen, so rows that
+  // shrink or disappear are overwritten with spaces
+  fn update_row(output: &mut String, line: usize, previous: &[char], row: &[char]) {
+    let cell = |cells: &[char], x: usize| cells.get(x).copied().unwrap_or(' ');
+    let width = row.len().max(previous.len());
+
+    let mut x = 0;
+    while x < width {
+      if cell(previous, x) == cell(row, x) {
+        x += 1;
+        continue;
+      }
+
+      // A run of changes ends once GAP cells in a row stay the same
+      let start = x;
+      let mut end = x + 1;
+      let mut unchanged = 0;
+      x += 1;
+      while x < width && unchanged < Terminal::GAP {
+        if cell(previous, x) == cell(row, x) {
+          unchanged += 1;
+        } else {
+          unchanged = 0;
+          end = x + 1;
+        }
+        x += 1;
+      }
+
+      let _ = write!(output, "\u{001b}[{};{}H", line, start + 1);
+      output.extend((start..end).map(|x| cell(row, x)));
+    }
+  }
+}
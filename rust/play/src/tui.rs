@@ -0,0 +1,611 @@
+use crate::options::Options;
+use crate::terminal::Terminal;
+use crate::Play;
+use gol_core::{
+  BoundingBox, BrailleRenderer, CycleDetector, HalfBlockRenderer, Renderer, TextRenderer, Universe,
+};
+use std::io::{self, Read};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const HELP: &str = "space pause, n step, +/- speed, arrows or hjkl move, HJKL pan, \
+                    enter or click toggle, z/x zoom, o open, w save, c clear, q quit";
+
+// Runs the world full screen until q is pressed, with the keys in HELP.
+// The screen is put back the way it was however the TUI ends
+pub fn run(options: &Options, world: Box<dyn Universe>) -> Result<(), String> {
+  let (columns, rows) = terminal_size()?;
+  let _raw_mode = RawMode::enable()?;
+  let keys = read_keys();
+
+  let mut tui = Tui::new(options, world, (columns, rows));
+  let mut next_tick = Instant::now();
+  let mut last_resize = Instant::now();
+  loop {
+    tui.draw()?;
+
+    // Without ticks to wait for, the terminal size is still checked
+    let timeout = if tui.running() {
+      next_tick.saturating_duration_since(Instant::now())
+    } else {
+      Tui::RESIZE_CHECK
+    };
+    match keys.recv_timeout(timeout) {
+      Ok(bytes) => {
+        for key in parse_keys(&bytes) {
+          if !tui.handle(key) {
+            return Ok(());
+          }
+        }
+      }
+      Err(RecvTimeoutError::Timeout) => {}
+      Err(RecvTimeoutError::Disconnected) => return Ok(()),
+    }
+
+    if tui.running() && Instant::now() >= next_tick {
+      tui.step();
+      next_tick = Instant::now() + tui.delay;
+    }
+    if last_resize.elapsed() >= Tui::RESIZE_CHECK {
+      tui.resize(terminal_size()?);
+      last_resize = Instant::now();
+    }
+  }
+}
+
+// How many cells each character holds, from the furthest out. The
+// closest level draws every cell two characters wide, as terminal
+// characters are about twice as tall as they are wide
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Zoom {
+  Braille,
+  HalfBlock,
+  Text,
+  Wide,
+}
+
+impl Zoom {
+  const LEVELS: [Zoom; 4] = [Zoom::Braille, Zoom::HalfBlock, Zoom::Text, Zoom::Wide];
+
+  fn zoom_in(self) -> Zoom {
+    let level = Zoom::LEVELS
+      .iter()
+      .position(|&zoom| zoom == self)
+      .unwrap_or(0);
+    Zoom::LEVELS[(level + 1).min(Zoom::LEVELS.len() - 1)]
+  }
+
+  fn zoom_out(self) -> Zoom {
+    let level = Zoom::LEVELS
+      .iter()
+      .position(|&zoom| zoom == self)
+      .unwrap_or(0);
+    Zoom::LEVELS[level.saturating_sub(1)]
+  }
+
+  // Columns and rows of cells in each character
+  fn cells(self) -> (i64, i64) {
+    match self {
+      Zoom::Braille => (2, 4),
+      Zoom::HalfBlock => (1, 2),
+      Zoom::Text | Zoom::Wide => (1, 1),
+    }
+  }
+
+  // Characters across each character's worth of cells
+  fn width(self) -> i64 {
+    match self {
+      Zoom::Wide => 2,
+      _ => 1,
+    }
+  }
+
+  fn render(self, universe: &dyn Universe, region: BoundingBox) -> String {
+    match self {
+      Zoom::Braille => BrailleRenderer.render_region(universe, region),
+      Zoom::HalfBlock => HalfBlockRenderer.render_region(universe, region),
+      Zoom::Text => TextRenderer.render_region(universe, region),
+      Zoom::Wide => {
+        let text = TextRenderer.render_region(universe, region);
+        let mut wide = String::with_capacity(text.len() * 6);
+        for char in text.chars() {
+          wide.push_str(match char {
+            'o' => "██",
+            '\n' => "\n",
+            _ => "  ",
+          });
+        }
+        wide
+      }
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Key {
+  Char(char),
+  Up,
+  Down,
+  Left,
+  Right,
+  Enter,
+  Backspace,
+  Escape,
+  Interrupt,
+  // Column and row of a left click, counted from 1
+  Click(i64, i64),
+}
+
+// A path being typed into the status bar
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Prompt {
+  Open(String),
+  Save(String),
+}
+
+struct Tui {
+  world: Box<dyn Universe>,
+  terminal: Terminal,
+  zoom: Zoom,
+  // Top left cell on screen, and the cell being edited
+  view: (i64, i64),
+  cursor: (i64, i64),
+  // Columns and rows of the terminal, the status bar included
+  screen: (i64, i64),
+  paused: bool,
+  delay: Duration,
+  stop_at: Option<u64>,
+  stop_on_cycle: bool,
+  detector: Option<CycleDetector>,
+  sparkline: bool,
+  prompt: Option<Prompt>,
+  message: String,
+  played: u64,
+  total_tick: f64,
+  drawn: u64,
+  total_render: f64,
+}
+
+impl Tui {
+  const RESIZE_CHECK: Duration = Duration::from_millis(500);
+  const SLOWEST: Duration = Duration::from_secs(4);
+  // Kept short, as the status bar shares its line with everything else
+  const SPARKLINE_WIDTH: usize = 20;
+
+  fn new(options: &Options, world: Box<dyn Universe>, screen: (i64, i64)) -> Tui {
+    let delay = options.fps.map_or(Duration::from_millis(100), |fps| {
+      Duration::from_secs_f64(1.0 / fps)
+    });
+    let view = world.viewport();
+    Tui {
+      world,
+      terminal: Terminal::default(),
+      zoom: Zoom::Text,
+      view,
+      cursor: view,
+      screen,
+      paused: false,
+      delay: delay.min(Tui::SLOWEST),
+      stop_at: options.generations,
+      stop_on_cycle: options.stop_on_cycle,
+      detector: options.stop_on_cycle.then(CycleDetector::new),
+      sparkline: options.sparkline,
+      prompt: None,
+      message: "? for help".to_string(),
+      played: 0,
+      total_tick: 0.0,
+      drawn: 0,
+      total_render: 0.0,
+    }
+  }
+
+  fn running(&self) -> bool {
+    !self.paused && self.prompt.is_none()
+  }
+
+  fn step(&mut self) {
+    let tick_start = Instant::now();
+    self.world._tick();
+    self.total_tick += tick_start.elapsed().as_secs_f64() * 1000.0;
+    self.played += 1;
+
+    if self
+      .stop_at
+      .is_some_and(|stop_at| self.world.tick() >= stop_at)
+    {
+      self.paused = true;
+      self.message = format!("Stopped at generation {}", self.world.tick());
+    }
+
+    // A cycle is reported once, and looked for again after an edit
+    let cycle = self
+      .detector
+      .as_mut()
+      .and_then(|detector| detector.observe(self.world.as_ref()));
+    if let Some(cycle) = cycle {
+      self.paused = true;
+      self.message = Play::describe(cycle);
+      self.detector = None;
+    }
+  }
+
+  // Edited generations didn't come from the ones before them, so the
+  // search for a cycle starts over
+  fn edited(&mut self) {
+    if self.stop_on_cycle {
+      self.detector = Some(CycleDetector::new());
+    }
+  }
+
+  // A new size leaves whatever was on screen in odd places, so the next
+  // frame starts from a cleared screen
+  fn resize(&mut self, screen: (i64, i64)) {
+    if screen != self.screen {
+      self.screen = screen;
+      self.terminal = Terminal::default();
+      print!("\u{001b}[2J");
+      self.follow_cursor();
+    }
+  }
+
+  // Cells across and down the screen under the status bar
+  fn region_size(&self) -> (i64, i64) {
+    let (cells_x, cells_y) = self.zoom.cells();
+    let columns = (self.screen.0 / self.zoom.width()).max(1);
+    let rows = (self.screen.1 - 1).max(1);
+    (columns * cells_x, rows * cells_y)
+  }
+
+  fn region(&self) -> BoundingBox {
+    let (columns, rows) = self.region_size();
+    BoundingBox {
+      min_x: self.view.0,
+      min_y: self.view.1,
+      max_x: self.view.0.saturating_add(columns - 1),
+      max_y: self.view.1.saturating_add(rows - 1),
+    }
+  }
+
+  // Moves the view as little as it takes to keep the cursor on screen
+  fn follow_cursor(&mut self) {
+    let (columns, rows) = self.region_size();
+    let (x, y) = self.cursor;
+    if x < self.view.0 {
+      self.view.0 = x;
+    } else if x > self.view.0.saturating_add(columns - 1) {
+      self.view.0 = x.saturating_sub(columns - 1);
+    }
+    if y < self.view.1 {
+      self.view.1 = y;
+    } else if y > self.view.1.saturating_add(rows - 1) {
+      self.view.1 = y.saturating_sub(rows - 1);
+    }
+  }
+
+  fn move_cursor(&mut self, x: i64, y: i64) {
+    self.cursor = (
+      self.cursor.0.saturating_add(x),
+      self.cursor.1.saturating_add(y),
+    );
+    self.follow_cursor();
+  }
+
+  // Pans by a quarter of the screen, taking the cursor along
+  fn pan(&mut self, x: i64, y: i64) {
+    let (columns, rows) = self.region_size();
+    let (x, y) = (x * (columns / 4).max(1), y * (rows / 4).max(1));
+    self.view = (self.view.0.saturating_add(x), self.view.1.saturating_add(y));
+    self.move_cursor(x, y);
+  }
+
+  // Keeps the cursor in the middle of the screen at the new zoom
+  fn set_zoom(&mut self, zoom: Zoom) {
+    self.zoom = zoom;
+    let (columns, rows) = self.region_size();
+    self.view = (
+      self.cursor.0.saturating_sub(columns / 2),
+      self.cursor.1.saturating_sub(rows / 2),
+    );
+  }
+
+  fn toggle(&mut self, x: i64, y: i64) {
+    let alive = !self.world.alive_at(x, y);
+    self.message = match self.world.set_alive(x, y, alive) {
+      Ok(()) => String::new(),
+      Err(error) => error.to_string(),
+    };
+    self.edited();
+  }
+
+  fn clear(&mut self) {
+    for (x, y) in self.world.live_cells() {
+      let _ = self.world.set_alive(x, y, false);
+    }
+    self.edited();
+  }
+
+  // Patterns are placed with their top left corner on the cursor
+  fn open(&mut self, path: &str) {
+    let (x, y) = self.cursor;
+    let placed = Play::load_pattern(Path::new(path)).and_then(|pattern| {
+      pattern
+        .place(self.world.as_mut(), x, y)
+        .map_err(|error| error.to_string())
+    });
+    self.message = match placed {
+      Ok(()) => format!("Placed {} at {},{}", path, x, y),
+      Err(error) => error,
+    };
+    self.edited();
+  }
+
+  fn save(&mut self, path: &str) {
+    self.message = match Play::save_pattern(Path::new(path), self.world.as_ref()) {
+      Ok(()) => format!("Saved {}", path),
+      Err(error) => error,
+    };
+  }
+
+  // Returns false once the TUI should quit
+  fn handle(&mut self, key: Key) -> bool {
+    if key == Key::Interrupt {
+      return false;
+    }
+    if let Some(prompt) = self.prompt.take() {
+      self.handle_prompt(prompt, key);
+      return true;
+    }
+
+    match key {
+      Key::Char('q') => return false,
+      Key::Char(' ') => self.paused = !self.paused,
+      Key::Char('n') => {
+        self.paused = true;
+        self.step();
+      }
+      Key::Char('+') | Key::Char('=') => self.delay /= 2,
+      Key::Char('-') => {
+        self.delay = (self.delay * 2)
+          .max(Duration::from_millis(1))
+          .min(Tui::SLOWEST)
+      }
+      Key::Up | Key::Char('k') => self.move_cursor(0, -1),
+      Key::Down | Key::Char('j') => self.move_cursor(0, 1),
+      Key::Left | Key::Char('h') => self.move_cursor(-1, 0),
+      Key::Right | Key::Char('l') => self.move_cursor(1, 0),
+      Key::Char('K') => self.pan(0, -1),
+      Key::Char('J') => self.pan(0, 1),
+      Key::Char('H') => self.pan(-1, 0),
+      Key::Char('L') => self.pan(1, 0),
+      Key::Enter | Key::Char('t') => self.toggle(self.cursor.0, self.cursor.1),
+      // Zoomed out, a click picks the top left cell of the character
+      Key::Click(column, row) if row >= 2 => {
+        let (cells_x, cells_y) = self.zoom.cells();
+        self.cursor = (
+          self.view.0 + (column - 1) / self.zoom.width() * cells_x,
+          self.view.1 + (row - 2) * cells_y,
+        );
+        self.toggle(self.cursor.0, self.cursor.1);
+      }
+      Key::Char('z') => self.set_zoom(self.zoom.zoom_in()),
+      Key::Char('x') => self.set_zoom(self.zoom.zoom_out()),
+      Key::Char('c') => self.clear(),
+      Key::Char('o') => self.prompt = Some(Prompt::Open(String::new())),
+      Key::Char('w') => self.prompt = Some(Prompt::Save(String::new())),
+      Key::Char('?') => self.message = HELP.to_string(),
+      _ => {}
+    }
+    true
+  }
+
+  fn handle_prompt(&mut self, prompt: Prompt, key: Key) {
+    let (mut path, open) = match prompt {
+      Prompt::Open(path) => (path, true),
+      Prompt::Save(path) => (path, false),
+    };
+    match key {
+      Key::Enter if open => self.open(&path),
+      Key::Enter => self.save(&path),
+      Key::Escape => {}
+      key => {
+        match key {
+          Key::Char(char) => path.push(char),
+          Key::Backspace => {
+            path.pop();
+          }
+          _ => {}
+        }
+        self.prompt = Some(if open {
+          Prompt::Open(path)
+        } else {
+          Prompt::Save(path)
+        });
+      }
+    }
+  }
+
+  fn status(&self) -> String {
+    match &self.prompt {
+      Some(Prompt::Open(path)) => return format!("Open pattern: {}", path),
+      Some(Prompt::Save(path)) => return format!("Save pattern: {}", path),
+      None => {}
+    }
+
+    let speed = match self.delay.as_secs_f64() {
+      0.0 => "max speed".to_string(),
+      delay => format!("{:.1} gen/s", 1.0 / delay),
+    };
+    let sparkline = match self.world.timeline().filter(|_| self.sparkline) {
+      Some(timeline) => format!(" {}", timeline.sparkline(Tui::SPARKLINE_WIDTH)),
+      None => String::new(),
+    };
+    // The most useful fields go first, as narrow terminals cut it short
+    format!(
+      "#{} | {} alive{} | {} | {} | {},{} | tick {} ms | render {} ms | {}",
+      self.world.tick(),
+      self.world.live_cells().len(),
+      sparkline,
+      if self.paused { "paused" } else { "running" },
+      speed,
+      self.cursor.0,
+      self.cursor.1,
+      Play::_f(self.total_tick / self.played.max(1) as f64),
+      Play::_f(self.total_render / self.drawn.max(1) as f64),
+      self.message
+    )
+  }
+
+  fn draw(&mut self) -> Result<(), String> {
+    let render_start = Instant::now();
+    let rendered = self.zoom.render(self.world.as_ref(), self.region());
+    self.total_render += render_start.elapsed().as_secs_f64() * 1000.0;
+    self.drawn += 1;
+
+    let status: String = self.status().chars().take(self.screen.0 as usize).collect();
+    let update = self.terminal.update(&rendered);
+
+    // The terminal's own cursor marks the cell being edited, or the end
+    // of the path being typed
+    let (column, row) = match self.prompt {
+      Some(_) => (status.chars().count() as i64 + 1, 1),
+      None => {
+        let (cells_x, cells_y) = self.zoom.cells();
+        (
+          (self.cursor.0 - self.view.0) / cells_x * self.zoom.width() + 1,
+          (self.cursor.1 - self.view.1) / cells_y + 2,
+        )
+      }
+    };
+    Play::write(&format!(
+      "\u{001b}[H{}\u{001b}[K{}\u{001b}[{};{}H",
+      status, update, row, column
+    ))
+  }
+}
+
+// Raw mode hands every key over as it is pressed, without echoing it.
+// The alternate screen keeps the shell's scrollback as it was, and mouse
+// reports come in the SGR form, which has no limit on the column
+struct RawMode {
+  saved: String,
+}
+
+impl RawMode {
+  fn enable() -> Result<RawMode, String> {
+    let saved = stty(&["-g"])?.trim().to_string();
+    stty(&["raw", "-echo"])?;
+    Play::write("\u{001b}[?1049h\u{001b}[?1000h\u{001b}[?1006h\u{001b}[2J")?;
+    Ok(RawMode { saved })
+  }
+}
+
+impl Drop for RawMode {
+  fn drop(&mut self) {
+    let _ = Play::write("\u{001b}[?1006l\u{001b}[?1000l\u{001b}[?25h\u{001b}[?1049l");
+    let _ = stty(&[&self.saved]);
+  }
+}
+
+// stty works on the terminal it gets as its standard input
+fn stty(args: &[&str]) -> Result<String, String> {
+  let output = Command::new("stty")
+    .args(args)
+    .stdin(Stdio::inherit())
+    .output()
+    .map_err(|error| format!("Couldn't run stty: {}", error))?;
+  if !output.status.success() {
+    return Err(format!(
+      "The TUI needs a terminal, but stty {} failed: {}",
+      args.join(" "),
+      String::from_utf8_lossy(&output.stderr).trim()
+    ));
+  }
+  Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Returns the columns and rows of the terminal
+fn terminal_size() -> Result<(i64, i64), String> {
+  let size = stty(&["size"])?;
+  let mut numbers = size.split_whitespace().map(str::parse::<i64>);
+  match (numbers.next(), numbers.next()) {
+    (Some(Ok(rows)), Some(Ok(columns))) => Ok((columns.max(1), rows.max(2))),
+    _ => Err(format!("Unexpected terminal size {}", size.trim())),
+  }
+}
+
+// Reading blocks, so keys are read on their own thread and sent over as
+// they arrive. The thread ends with the process
+fn read_keys() -> Receiver<Vec<u8>> {
+  let (sender, receiver) = mpsc::channel();
+  thread::spawn(move || {
+    let mut stdin = io::stdin();
+    let mut buffer = [0; 64];
+    while let Ok(read @ 1..) = stdin.read(&mut buffer) {
+      if sender.send(buffer[..read].to_vec()).is_err() {
+        break;
+      }
+    }
+  });
+  receiver
+}
+
+// Escape on its own is the escape key, while escape followed by [
+// starts a sequence such as an arrow key or a mouse report
+fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+  let mut keys = Vec::new();
+  let mut i = 0;
+  while i < bytes.len() {
+    match bytes[i] {
+      0x1b if bytes.get(i + 1) == Some(&b'[') => {
+        // The sequence ends at the first byte from @ to ~
+        let start = i + 2;
+        let end = (start..bytes.len())
+          .find(|&j| (0x40..=0x7e).contains(&bytes[j]))
+          .unwrap_or(bytes.len() - 1);
+        match &bytes[start..=end] {
+          b"A" => keys.push(Key::Up),
+          b"B" => keys.push(Key::Down),
+          b"C" => keys.push(Key::Right),
+          b"D" => keys.push(Key::Left),
+          [b'<', report @ .., b'M'] => keys.extend(parse_click(report)),
+          _ => {}
+        }
+        i = end + 1;
+        continue;
+      }
+      0x1b => keys.push(Key::Escape),
+      0x03 => keys.push(Key::Interrupt),
+      b'\r' | b'\n' => keys.push(Key::Enter),
+      0x7f | 0x08 => keys.push(Key::Backspace),
+      byte if byte < 0x20 => {}
+      _ => {
+        // Characters run up to the next control byte
+        let end = (i..bytes.len())
+          .find(|&j| bytes[j] < 0x20 || bytes[j] == 0x7f)
+          .unwrap_or(bytes.len());
+        keys.extend(
+          String::from_utf8_lossy(&bytes[i..end])
+            .chars()
+            .map(Key::Char),
+        );
+        i = end;
+        continue;
+      }
+    }
+    i += 1;
+  }
+  keys
+}
+
+// Reads `button;column;row` from a press, keeping left clicks only
+fn parse_click(report: &[u8]) -> Option<Key> {
+  let report = std::str::from_utf8(report).ok()?;
+  let mut fields = report.split(';').map(str::parse::<i64>);
+  match (fields.next(), fields.next(), fields.next()) {
+    (Some(Ok(0)), Some(Ok(column)), Some(Ok(row))) => Some(Key::Click(column, row)),
+    _ => None,
+  }
+}